@@ -1 +1,3 @@
 mod macros;
+
+pub mod vm;
@@ -0,0 +1,356 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+
+use super::{Instruction, Program};
+
+/* ---------- */
+
+/// How an instruction may move the program counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Branch {
+    /// Always goes on with the following instruction.
+    Next,
+    /// Always jumps by the given offset.
+    Jump(isize),
+    /// Either jumps by the given offset or goes on with the following instruction.
+    Cond(isize),
+    /// Stops the machine.
+    Halt,
+}
+
+/* ---------- */
+
+/// Gives the static analyses of this module insight into an instruction set.
+pub trait Analyze: Instruction + Display {
+    fn branch(&self) -> Branch;
+
+    /// Formats the instruction with its jump offset replaced by `target`. Only called on
+    /// instructions that do jump.
+    fn fmt_target(&self, target: &str) -> String;
+}
+
+/* ---------- */
+
+/// Where control goes when leaving a basic block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Target {
+    Block(usize),
+    /// The program counter leaves the program, or an instruction halts the machine.
+    Exit,
+}
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Fallthrough,
+    Taken,
+}
+
+/* ---------- */
+
+/// A run of instructions only ever entered from the first one and left from the last one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    /// Index of the first instruction.
+    pub start: usize,
+    /// Index following the last instruction.
+    pub end: usize,
+    pub succs: Vec<(Target, EdgeKind)>,
+}
+
+/* ---------- */
+
+/// A natural loop, identified by its header block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub header: usize,
+    /// Every block of the loop, header included, sorted.
+    pub blocks: Vec<usize>,
+}
+
+/* ---------- */
+
+/// The control flow graph of a program, the entry being the first block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cfg {
+    blocks: Vec<Block>,
+}
+
+impl Cfg {
+    pub fn new<I: Analyze>(prog: &Program<I>) -> Self {
+        let len = prog.len();
+        let mut leaders = vec![false; len + 1];
+        leaders[0] = true;
+
+        for (idx, instr) in prog.iter().enumerate() {
+            match instr.branch() {
+                Branch::Next => continue,
+                Branch::Jump(off) | Branch::Cond(off) => {
+                    if let Some(target) = jump_target(idx, off, len) {
+                        leaders[target] = true;
+                    }
+                }
+                Branch::Halt => (),
+            }
+
+            leaders[idx + 1] = true;
+        }
+
+        let starts = (0..len).filter(|&idx| leaders[idx]).collect::<Vec<_>>();
+        let block_of = |pc: Option<usize>| match pc {
+            Some(pc) if pc < len => Target::Block(starts.partition_point(|&start| start <= pc) - 1),
+            _ => Target::Exit,
+        };
+
+        let blocks = starts
+            .iter()
+            .enumerate()
+            .map(|(id, &start)| {
+                let end = starts.get(id + 1).copied().unwrap_or(len);
+                let last = end - 1;
+                let next = block_of(Some(end));
+
+                let succs = match prog[last].branch() {
+                    Branch::Next => vec![(next, EdgeKind::Fallthrough)],
+                    Branch::Jump(off) => {
+                        vec![(block_of(jump_target(last, off, len)), EdgeKind::Taken)]
+                    }
+                    Branch::Cond(off) => vec![
+                        (block_of(jump_target(last, off, len)), EdgeKind::Taken),
+                        (next, EdgeKind::Fallthrough),
+                    ],
+                    Branch::Halt => vec![(Target::Exit, EdgeKind::Fallthrough)],
+                };
+
+                Block { start, end, succs }
+            })
+            .collect();
+
+        Self { blocks }
+    }
+
+    #[inline]
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Returns the block holding the instruction at `pc`.
+    pub fn block_at(&self, pc: usize) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|block| (block.start..block.end).contains(&pc))
+    }
+
+    fn preds(&self) -> Vec<Vec<usize>> {
+        let mut preds = vec![Vec::new(); self.blocks.len()];
+
+        for (id, block) in self.blocks.iter().enumerate() {
+            for &(succ, _) in &block.succs {
+                if let Target::Block(succ) = succ {
+                    preds[succ].push(id);
+                }
+            }
+        }
+
+        preds
+    }
+
+    /// Computes, for every block, the set of blocks dominating it. Blocks unreachable from the
+    /// entry end up with an empty set.
+    pub fn dominators(&self) -> Vec<Vec<bool>> {
+        let count = self.blocks.len();
+        let preds = self.preds();
+        let reachable = self.reachable();
+
+        let mut doms = reachable
+            .iter()
+            .map(|&reachable| vec![reachable; count])
+            .collect::<Vec<_>>();
+
+        if let Some(entry) = doms.first_mut() {
+            *entry = vec![false; count];
+            entry[0] = true;
+        }
+
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for id in (1..count).filter(|&id| reachable[id]) {
+                let mut dom = vec![true; count];
+
+                for &pred in preds[id].iter().filter(|&&pred| reachable[pred]) {
+                    dom.iter_mut()
+                        .zip(&doms[pred])
+                        .for_each(|(dom, &pred_dom)| *dom &= pred_dom);
+                }
+
+                dom[id] = true;
+
+                if dom != doms[id] {
+                    doms[id] = dom;
+                    changed = true;
+                }
+            }
+        }
+
+        doms
+    }
+
+    fn reachable(&self) -> Vec<bool> {
+        let mut seen = vec![false; self.blocks.len()];
+        let mut stack = Vec::new();
+
+        if !self.blocks.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(id) = stack.pop() {
+            if std::mem::replace(&mut seen[id], true) {
+                continue;
+            }
+
+            for &(succ, _) in &self.blocks[id].succs {
+                if let Target::Block(succ) = succ {
+                    stack.push(succ);
+                }
+            }
+        }
+
+        seen
+    }
+
+    /// Finds the natural loops of the program: every edge going back to a block dominating its
+    /// source closes a loop. Loops sharing a header are merged.
+    pub fn loops(&self) -> Vec<Loop> {
+        let doms = self.dominators();
+        let preds = self.preds();
+        let mut loops = BTreeMap::<usize, Vec<bool>>::new();
+
+        for (id, block) in self.blocks.iter().enumerate() {
+            for &(succ, _) in &block.succs {
+                let header = match succ {
+                    Target::Block(header) if doms[id][header] => header,
+                    _ => continue,
+                };
+
+                let body = loops
+                    .entry(header)
+                    .or_insert_with(|| vec![false; self.blocks.len()]);
+                body[header] = true;
+
+                let mut stack = vec![id];
+
+                while let Some(node) = stack.pop() {
+                    if !std::mem::replace(&mut body[node], true) {
+                        stack.extend(preds[node].iter().copied());
+                    }
+                }
+            }
+        }
+
+        loops
+            .into_iter()
+            .map(|(header, body)| Loop {
+                header,
+                blocks: (0..body.len()).filter(|&id| body[id]).collect(),
+            })
+            .collect()
+    }
+
+    /// Exports the graph in the Graphviz DOT format, each node listing its instructions.
+    pub fn to_dot<I: Analyze>(&self, prog: &Program<I>) -> String {
+        let mut dot = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+
+        for (id, block) in self.blocks.iter().enumerate() {
+            let instrs = (block.start..block.end)
+                .map(|pc| format!("{pc}: {}\\l", prog[pc]))
+                .collect::<String>();
+
+            writeln!(dot, "    b{id} [label=\"{instrs}\"];").ok();
+        }
+
+        writeln!(dot, "    exit [shape=doublecircle];").ok();
+
+        for (id, block) in self.blocks.iter().enumerate() {
+            for &(succ, kind) in &block.succs {
+                let succ = match succ {
+                    Target::Block(succ) => format!("b{succ}"),
+                    Target::Exit => String::from("exit"),
+                };
+
+                let style = match kind {
+                    EdgeKind::Fallthrough => "",
+                    EdgeKind::Taken => " [style=bold]",
+                };
+
+                writeln!(dot, "    b{id} -> {succ}{style};").ok();
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/* ---------- */
+
+#[inline]
+fn jump_target(pc: usize, off: isize, len: usize) -> Option<usize> {
+    pc.checked_add_signed(off).filter(|&target| target < len)
+}
+
+/* ---------- */
+
+/// Pretty-prints a program, jump targets being replaced by labels and basic blocks separated
+/// by blank lines.
+pub fn disassemble<I: Analyze>(prog: &Program<I>) -> String {
+    let len = prog.len();
+    let mut labels = BTreeMap::new();
+
+    for (idx, instr) in prog.iter().enumerate() {
+        if let Branch::Jump(off) | Branch::Cond(off) = instr.branch() {
+            if let Some(target) = jump_target(idx, off, len) {
+                labels.insert(target, String::new());
+            }
+        }
+    }
+
+    labels
+        .values_mut()
+        .enumerate()
+        .for_each(|(nb, label)| *label = format!("L{nb}"));
+
+    let cfg = Cfg::new(prog);
+    let mut out = String::new();
+
+    for (idx, instr) in prog.iter().enumerate() {
+        if idx > 0 && cfg.blocks().iter().any(|block| block.start == idx) {
+            out.push('\n');
+        }
+
+        let label = labels
+            .get(&idx)
+            .map(|label| format!("{label}:"))
+            .unwrap_or_default();
+
+        let asm = match instr.branch() {
+            Branch::Jump(off) | Branch::Cond(off) => {
+                let target = match idx.checked_add_signed(off) {
+                    Some(target) if target < len => labels[&target].clone(),
+                    Some(target) if target == len => String::from("end"),
+                    _ => format!("out of range ({off:+})"),
+                };
+
+                instr.fmt_target(&target)
+            }
+            Branch::Next | Branch::Halt => instr.to_string(),
+        };
+
+        writeln!(out, "{label:<6}{idx:>4}  {asm}").ok();
+    }
+
+    out
+}
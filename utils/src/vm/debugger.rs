@@ -0,0 +1,320 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::{self, Write};
+
+use super::{Instruction, Machine, Program, Reg, Registers, State, VmError, Word};
+
+/* ---------- */
+
+/// Number of trace entries kept by default, the oldest ones being dropped past that.
+pub const TRACE_CAPACITY: usize = 1 << 20;
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Cmp {
+    #[inline]
+    pub fn eval(self, left: Word, right: Word) -> bool {
+        match self {
+            Self::Eq => left == right,
+            Self::Ne => left != right,
+            Self::Lt => left < right,
+            Self::Le => left <= right,
+            Self::Gt => left > right,
+            Self::Ge => left >= right,
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "==" => Ok(Self::Eq),
+            "!=" => Ok(Self::Ne),
+            "<" => Ok(Self::Lt),
+            "<=" => Ok(Self::Le),
+            ">" => Ok(Self::Gt),
+            ">=" => Ok(Self::Ge),
+            _ => Err(format!("invalid comparison '{s}'")),
+        }
+    }
+}
+
+impl Display for Cmp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let s = match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stops before executing the instruction at the given index.
+    At(usize),
+    /// Stops as soon as the register compares to the value.
+    When(Reg, Cmp, Word),
+}
+
+impl Breakpoint {
+    #[inline]
+    fn is_hit(self, machine: &Machine) -> bool {
+        match self {
+            Self::At(pc) => machine.pc() == pc,
+            Self::When(reg, cmp, value) => cmp.eval(machine.reg(reg), value),
+        }
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::At(pc) => write!(f, "at {pc}"),
+            Self::When(reg, cmp, value) => write!(f, "when {} {cmp} {value}", reg.name()),
+        }
+    }
+}
+
+/* ---------- */
+
+/// The state of the machine right before an instruction was executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry<I> {
+    pub step: u64,
+    pub pc: usize,
+    pub instr: I,
+    pub regs: Registers,
+}
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Halted,
+    /// Holds the index of the breakpoint that was hit.
+    Breakpoint(usize),
+    /// The machine failed, or ran out of steps, see [`Machine::step_checked`].
+    Error(VmError),
+}
+
+/* ---------- */
+
+/// Wraps a [`Machine`] to run a program step by step, stop on breakpoints and keep track of
+/// what was executed.
+///
+/// Execution goes through the checked methods of the machine, so a failing instruction or a
+/// program outliving the step limit of the machine stops the debugger instead of panicking or
+/// running forever.
+pub struct Debugger<'a, I> {
+    prog: &'a Program<I>,
+    machine: Machine,
+    breakpoints: Vec<Breakpoint>,
+    watches: Vec<Reg>,
+    recording: bool,
+    trace: VecDeque<TraceEntry<I>>,
+    trace_capacity: usize,
+    counts: Vec<u64>,
+}
+
+impl<'a, I: Instruction + Clone> Debugger<'a, I> {
+    pub fn new(prog: &'a Program<I>, machine: Machine) -> Self {
+        Self {
+            prog,
+            machine,
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+            recording: false,
+            trace: VecDeque::new(),
+            trace_capacity: TRACE_CAPACITY,
+            counts: vec![0; prog.len()],
+        }
+    }
+
+    #[inline]
+    pub fn program(&self) -> &'a Program<I> {
+        self.prog
+    }
+
+    #[inline]
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    #[inline]
+    pub fn machine_mut(&mut self) -> &mut Machine {
+        &mut self.machine
+    }
+
+    #[inline]
+    pub fn add_breakpoint(&mut self, bp: Breakpoint) {
+        self.breakpoints.push(bp)
+    }
+
+    /// Removes the breakpoint at the given index, as listed by [`Debugger::breakpoints`].
+    #[inline]
+    pub fn remove_breakpoint(&mut self, idx: usize) -> Option<Breakpoint> {
+        (idx < self.breakpoints.len()).then(|| self.breakpoints.remove(idx))
+    }
+
+    #[inline]
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    #[inline]
+    pub fn watch(&mut self, reg: Reg) {
+        if !self.watches.contains(&reg) {
+            self.watches.push(reg)
+        }
+    }
+
+    #[inline]
+    pub fn unwatch(&mut self, reg: Reg) {
+        self.watches.retain(|&watched| watched != reg)
+    }
+
+    /// Returns the current value of every watched register.
+    pub fn watched(&self) -> Vec<(Reg, Word)> {
+        self.watches
+            .iter()
+            .map(|&reg| (reg, self.machine.reg(reg)))
+            .collect()
+    }
+
+    /// Starts or stops recording the execution trace.
+    #[inline]
+    pub fn record(&mut self, recording: bool) {
+        self.recording = recording
+    }
+
+    /// Bounds the recorded trace to its `capacity` most recent entries.
+    pub fn set_trace_capacity(&mut self, capacity: usize) {
+        self.trace_capacity = capacity;
+
+        while self.trace.len() > capacity {
+            self.trace.pop_front();
+        }
+    }
+
+    #[inline]
+    pub fn trace(&self) -> &VecDeque<TraceEntry<I>> {
+        &self.trace
+    }
+
+    /// How many times each instruction of the program was executed.
+    #[inline]
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// Returns the `n` most executed instructions, as `(index, count)` pairs.
+    pub fn hot_spots(&self, n: usize) -> Vec<(usize, u64)> {
+        let mut counts = self
+            .counts
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|&(_, count)| count > 0)
+            .collect::<Vec<_>>();
+
+        counts.sort_by(|(l_idx, l_count), (r_idx, r_count)| {
+            r_count.cmp(l_count).then(l_idx.cmp(r_idx))
+        });
+        counts.truncate(n);
+        counts
+    }
+
+    /// Executes a single instruction, ignoring breakpoints.
+    pub fn step(&mut self) -> Result<State, VmError> {
+        let pc = self.machine.pc();
+        let entry = match (self.machine.state(), self.prog.get(pc)) {
+            (State::Running, Some(instr)) if self.recording => Some(TraceEntry {
+                step: self.machine.steps(),
+                pc,
+                instr: instr.clone(),
+                regs: self.machine.registers().clone(),
+            }),
+            _ => None,
+        };
+        let executed = self.machine.steps();
+        let state = self.machine.step_checked(self.prog)?;
+
+        if self.machine.steps() > executed {
+            self.counts[pc] += 1;
+        }
+
+        if let Some(entry) = entry.filter(|_| self.trace_capacity > 0) {
+            if self.trace.len() == self.trace_capacity {
+                self.trace.pop_front();
+            }
+
+            self.trace.push_back(entry);
+        }
+
+        Ok(state)
+    }
+
+    /// Runs until a breakpoint is hit, the machine halts or fails. At least one instruction is
+    /// executed, so that continuing from a breakpoint doesn't stop right away.
+    pub fn resume(&mut self) -> Stop {
+        loop {
+            match self.step() {
+                Ok(State::Halted) => return Stop::Halted,
+                Ok(State::Running) => (),
+                Err(err) => return Stop::Error(err),
+            }
+
+            let hit = self
+                .breakpoints
+                .iter()
+                .position(|bp| bp.is_hit(&self.machine));
+
+            if let Some(idx) = hit {
+                return Stop::Breakpoint(idx);
+            }
+        }
+    }
+}
+
+impl<I: Display> Debugger<'_, I> {
+    /// Writes the recorded trace, one executed instruction per line.
+    pub fn write_trace(&self, out: &mut impl Write) -> io::Result<()> {
+        for entry in &self.trace {
+            let regs = entry
+                .regs
+                .values()
+                .iter()
+                .enumerate()
+                .map(|(idx, val)| format!("{}={val}", Reg(idx).name()))
+                .collect::<Vec<_>>();
+
+            let instr = entry.instr.to_string();
+
+            writeln!(
+                out,
+                "{:>8} {:>4}  {instr:<16} {}",
+                entry.step,
+                entry.pc,
+                regs.join(" ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::{Flow, Instruction, Overflow, Program, Reg, Registers, Word};
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Running,
+    Halted,
+}
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VmError {
+    /// The instruction at `pc` jumped to `target`, outside of the program.
+    PcOutOfRange { pc: usize, target: isize },
+    /// The instruction at `pc` overflowed the register `reg`.
+    RegisterOverflow { pc: usize, reg: Reg },
+    /// The machine executed its whole step budget without halting.
+    StepLimitExceeded(u64),
+}
+
+impl Display for VmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::PcOutOfRange { pc, target } => {
+                write!(f, "instruction {pc} jumps out of the program, to {target}")
+            }
+            Self::RegisterOverflow { pc, reg } => {
+                write!(f, "instruction {pc} overflows register {}", reg.name())
+            }
+            Self::StepLimitExceeded(limit) => write!(f, "still running after {limit} steps"),
+        }
+    }
+}
+
+impl Error for VmError {}
+
+/* ---------- */
+
+/// Runs a [`Program`] against a register file.
+///
+/// The machine halts when an instruction asks for it, or when the program counter leaves the
+/// program, in either direction.
+///
+/// The `*_checked` flavours of the execution methods are stricter: they report overflowing
+/// registers, jumps leading anywhere else than inside the program or right after its end, and
+/// programs running longer than the step limit, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Machine {
+    regs: Registers,
+    pc: usize,
+    steps: u64,
+    step_limit: Option<u64>,
+    state: State,
+}
+
+impl Machine {
+    #[inline]
+    pub fn new(register_count: usize) -> Self {
+        Self {
+            regs: Registers::new(register_count),
+            pc: 0,
+            steps: 0,
+            step_limit: None,
+            state: State::Running,
+        }
+    }
+
+    /// Bounds the number of steps the checked execution methods may run for, in total.
+    #[inline]
+    pub fn with_step_limit(mut self, limit: u64) -> Self {
+        self.step_limit = Some(limit);
+        self
+    }
+
+    #[inline]
+    pub fn set_step_limit(&mut self, limit: Option<u64>) {
+        self.step_limit = limit
+    }
+
+    #[inline]
+    pub fn reg(&self, reg: Reg) -> Word {
+        self.regs[reg]
+    }
+
+    #[inline]
+    pub fn set_reg(&mut self, reg: Reg, val: Word) {
+        self.regs[reg] = val
+    }
+
+    #[inline]
+    pub fn registers(&self) -> &Registers {
+        &self.regs
+    }
+
+    /// The index of the next instruction to execute.
+    #[inline]
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// The number of instructions executed so far.
+    #[inline]
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    #[inline]
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Executes a single instruction.
+    pub fn step<I: Instruction>(&mut self, prog: &Program<I>) -> State {
+        if self.state == State::Halted {
+            return self.state;
+        }
+
+        let instr = match prog.get(self.pc) {
+            Some(instr) => instr,
+            None => {
+                self.state = State::Halted;
+                return self.state;
+            }
+        };
+
+        self.steps += 1;
+
+        match instr.execute(&mut self.regs) {
+            Flow::Next => self.pc += 1,
+            Flow::Jump(off) => match self.pc.checked_add_signed(off) {
+                Some(pc) => self.pc = pc,
+                None => self.state = State::Halted,
+            },
+            Flow::Halt => self.state = State::Halted,
+        }
+
+        if self.pc >= prog.len() {
+            self.state = State::Halted
+        }
+
+        self.state
+    }
+
+    /// Runs the program until the machine halts.
+    pub fn run<I: Instruction>(&mut self, prog: &Program<I>) {
+        while self.step(prog) == State::Running {}
+    }
+
+    /// Runs the program until `pred` holds, checked before every instruction, or until the
+    /// machine halts. Returns whether `pred` was met.
+    pub fn run_until<I, F>(&mut self, prog: &Program<I>, mut pred: F) -> bool
+    where
+        I: Instruction,
+        F: FnMut(&Self) -> bool,
+    {
+        loop {
+            if pred(self) {
+                return true;
+            }
+
+            if self.state == State::Halted {
+                return false;
+            }
+
+            self.step(prog);
+        }
+    }
+
+    /// Executes a single instruction, see [`Machine`] for what is checked.
+    pub fn step_checked<I: Instruction>(&mut self, prog: &Program<I>) -> Result<State, VmError> {
+        if self.state == State::Halted {
+            return Ok(self.state);
+        }
+
+        if let Some(limit) = self.step_limit.filter(|&limit| self.steps >= limit) {
+            return Err(VmError::StepLimitExceeded(limit));
+        }
+
+        let pc = self.pc;
+        let instr = match prog.get(pc) {
+            Some(instr) => instr,
+            None => {
+                self.state = State::Halted;
+                return Ok(self.state);
+            }
+        };
+
+        let flow = instr
+            .try_execute(&mut self.regs)
+            .map_err(|Overflow(reg)| VmError::RegisterOverflow { pc, reg })?;
+
+        self.steps += 1;
+
+        match flow {
+            Flow::Next => self.pc += 1,
+            Flow::Jump(off) => match pc.checked_add_signed(off) {
+                Some(target) if target <= prog.len() => self.pc = target,
+                _ => {
+                    self.state = State::Halted;
+                    let target = (pc as isize).saturating_add(off);
+                    return Err(VmError::PcOutOfRange { pc, target });
+                }
+            },
+            Flow::Halt => self.state = State::Halted,
+        }
+
+        if self.pc >= prog.len() {
+            self.state = State::Halted
+        }
+
+        Ok(self.state)
+    }
+
+    /// Runs the program until the machine halts, or fails.
+    pub fn run_checked<I: Instruction>(&mut self, prog: &Program<I>) -> Result<(), VmError> {
+        while self.step_checked(prog)? == State::Running {}
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+//! A small register machine, meant to be shared by the puzzles running programs written in some
+//! assembly-like language.
+//!
+//! A puzzle only has to describe its instruction set through the [`Instruction`] trait, the
+//! loading of the [`Program`] and the fetch/execute loop of the [`Machine`] being handled here.
+
+mod analysis;
+mod debugger;
+mod machine;
+mod program;
+mod registers;
+
+#[cfg(test)]
+mod test;
+
+pub use analysis::*;
+pub use debugger::*;
+pub use machine::*;
+pub use program::*;
+pub use registers::*;
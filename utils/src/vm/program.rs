@@ -0,0 +1,111 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::ops::Index;
+
+use super::{Reg, Registers};
+
+/* ---------- */
+
+/// What the [`super::Machine`] does once an instruction has been executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Goes on with the following instruction.
+    Next,
+    /// Moves the program counter by the given offset.
+    Jump(isize),
+    /// Stops the machine.
+    Halt,
+}
+
+/* ---------- */
+
+/// Raised by an instruction whose result doesn't fit in the register it writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow(pub Reg);
+
+/* ---------- */
+
+/// An instruction set, implemented by the instruction type of a puzzle.
+pub trait Instruction: Sized {
+    /// Parses a single line of assembly.
+    fn parse(line: &str) -> Result<Self, String>;
+
+    /// Executes the instruction against the register file.
+    fn execute(&self, regs: &mut Registers) -> Flow;
+
+    /// Same as [`Instruction::execute`], but leaves the registers untouched and reports the
+    /// overflow instead of wrapping or panicking. Used by [`super::Machine::step_checked`].
+    fn try_execute(&self, regs: &mut Registers) -> Result<Flow, Overflow> {
+        Ok(self.execute(regs))
+    }
+}
+
+/* ---------- */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The 1-based line the error comes from.
+    pub line: usize,
+    pub msg: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+/* ---------- */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program<I>(Vec<I>);
+
+impl<I: Instruction> Program<I> {
+    /// Parses one instruction per line, blank lines being skipped.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| {
+                I::parse(line.trim()).map_err(|msg| ParseError { line: idx + 1, msg })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+impl<I> Program<I> {
+    #[inline]
+    pub fn new(instrs: Vec<I>) -> Self {
+        Self(instrs)
+    }
+
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<&I> {
+        self.0.get(idx)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &I> {
+        self.0.iter()
+    }
+}
+
+impl<I> Index<usize> for Program<I> {
+    type Output = I;
+
+    #[inline]
+    fn index(&self, idx: usize) -> &Self::Output {
+        &self.0[idx]
+    }
+}
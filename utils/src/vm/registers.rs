@@ -0,0 +1,79 @@
+use std::ops::{Index, IndexMut};
+
+/* ---------- */
+
+/// The value held by a register.
+pub type Word = i64;
+
+/* ---------- */
+
+/// The index of a register in a [`Registers`] file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reg(pub usize);
+
+impl Reg {
+    /// Parses a register named by a single lowercase letter, `a` being the first one. A trailing
+    /// `,` is ignored, as most puzzles separate operands with one.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let name = s.strip_suffix(',').unwrap_or(s);
+
+        match name.as_bytes() {
+            &[c] if c.is_ascii_lowercase() => Ok(Self((c - b'a') as usize)),
+            _ => Err(format!("invalid register '{s}'")),
+        }
+    }
+
+    #[inline]
+    pub fn name(self) -> char {
+        (b'a' + self.0 as u8) as char
+    }
+}
+
+/* ---------- */
+
+/// A fixed size register file, every register starting at 0.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Registers(Vec<Word>);
+
+impl Registers {
+    #[inline]
+    pub fn new(count: usize) -> Self {
+        Self(vec![0; count])
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, reg: Reg) -> Option<Word> {
+        self.0.get(reg.0).copied()
+    }
+
+    #[inline]
+    pub fn values(&self) -> &[Word] {
+        &self.0
+    }
+}
+
+impl Index<Reg> for Registers {
+    type Output = Word;
+
+    #[inline]
+    fn index(&self, reg: Reg) -> &Self::Output {
+        &self.0[reg.0]
+    }
+}
+
+impl IndexMut<Reg> for Registers {
+    #[inline]
+    fn index_mut(&mut self, reg: Reg) -> &mut Self::Output {
+        &mut self.0[reg.0]
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::*;
+
+/* ---------- */
+
+const A: Reg = Reg(0);
+const B: Reg = Reg(1);
+
+/// A minimal instruction set, enough to exercise every kind of control flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Toy {
+    Set(Reg, Word),
+    Add(Reg, Word),
+    Jump(isize),
+    JumpIfNonZero(Reg, isize),
+    Halt,
+}
+
+impl Instruction for Toy {
+    fn parse(line: &str) -> Result<Self, String> {
+        let num = |s: &str| s.parse::<Word>().map_err(|_| format!("bad number {s}"));
+        let off = |s: &str| s.parse::<isize>().map_err(|_| format!("bad offset {s}"));
+
+        match line.split_ascii_whitespace().collect::<Vec<_>>().as_slice() {
+            ["set", reg, val] => Ok(Self::Set(Reg::parse(reg)?, num(val)?)),
+            ["add", reg, val] => Ok(Self::Add(Reg::parse(reg)?, num(val)?)),
+            ["jmp", off_str] => Ok(Self::Jump(off(off_str)?)),
+            ["jnz", reg, off_str] => Ok(Self::JumpIfNonZero(Reg::parse(reg)?, off(off_str)?)),
+            ["hlt"] => Ok(Self::Halt),
+            _ => Err(format!("unknown instruction {line}")),
+        }
+    }
+
+    fn execute(&self, regs: &mut Registers) -> Flow {
+        self.try_execute(regs).expect("no register overflow")
+    }
+
+    fn try_execute(&self, regs: &mut Registers) -> Result<Flow, Overflow> {
+        match *self {
+            Self::Set(reg, val) => regs[reg] = val,
+            Self::Add(reg, val) => regs[reg] = regs[reg].checked_add(val).ok_or(Overflow(reg))?,
+            Self::Jump(off) => return Ok(Flow::Jump(off)),
+            Self::JumpIfNonZero(reg, off) if regs[reg] != 0 => return Ok(Flow::Jump(off)),
+            Self::JumpIfNonZero(..) => (),
+            Self::Halt => return Ok(Flow::Halt),
+        }
+
+        Ok(Flow::Next)
+    }
+}
+
+impl Display for Toy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Set(reg, val) => write!(f, "set {} {val}", reg.name()),
+            Self::Add(reg, val) => write!(f, "add {} {val}", reg.name()),
+            Self::Jump(off) => write!(f, "jmp {off:+}"),
+            Self::JumpIfNonZero(reg, off) => write!(f, "jnz {} {off:+}", reg.name()),
+            Self::Halt => write!(f, "hlt"),
+        }
+    }
+}
+
+impl Analyze for Toy {
+    fn branch(&self) -> Branch {
+        match *self {
+            Self::Set(..) | Self::Add(..) => Branch::Next,
+            Self::Jump(off) => Branch::Jump(off),
+            Self::JumpIfNonZero(_, off) => Branch::Cond(off),
+            Self::Halt => Branch::Halt,
+        }
+    }
+
+    fn fmt_target(&self, target: &str) -> String {
+        match self {
+            Self::Jump(_) => format!("jmp {target}"),
+            Self::JumpIfNonZero(reg, _) => format!("jnz {} {target}", reg.name()),
+            _ => self.to_string(),
+        }
+    }
+}
+
+fn parse(prog: &str) -> Program<Toy> {
+    Program::parse(prog).unwrap()
+}
+
+/* ---------- */
+
+#[test]
+fn test_step() {
+    let prog = parse("set a 3\nadd b 2\nadd a -1\njnz a -2");
+    let mut machine = Machine::new(2);
+
+    assert_eq!(machine.step(&prog), State::Running);
+    assert_eq!((machine.pc(), machine.reg(A)), (1, 3));
+
+    machine.run(&prog);
+    assert_eq!(machine.state(), State::Halted);
+    assert_eq!(machine.registers().values(), [0, 6]);
+    assert_eq!(machine.steps(), 10);
+
+    // A halted machine stays put.
+    assert_eq!(machine.step(&prog), State::Halted);
+    assert_eq!(machine.steps(), 10);
+
+    let mut machine = Machine::new(2);
+    machine.run(&parse("hlt\nset a 1"));
+    assert_eq!((machine.reg(A), machine.steps()), (0, 1));
+
+    // Jumping before the first instruction halts the unchecked machine.
+    let mut machine = Machine::new(2);
+    machine.run(&parse("add a 1\njmp -5"));
+    assert_eq!((machine.state(), machine.reg(A)), (State::Halted, 1));
+}
+
+#[test]
+fn test_run_until() {
+    let prog = parse("set a 3\nadd b 2\nadd a -1\njnz a -2");
+
+    let mut machine = Machine::new(2);
+    assert!(machine.run_until(&prog, |machine| machine.reg(B) == 4));
+    assert_eq!((machine.pc(), machine.reg(A)), (2, 2));
+
+    // The predicate is checked before the first instruction too.
+    assert!(machine.run_until(&prog, |_| true));
+    assert_eq!(machine.pc(), 2);
+
+    assert!(!machine.run_until(&prog, |machine| machine.reg(B) > 6));
+    assert_eq!(machine.state(), State::Halted);
+}
+
+#[test]
+fn test_checked() {
+    let mut machine = Machine::new(2);
+    assert_eq!(
+        machine.run_checked(&parse("add a 1\njmp -5")),
+        Err(VmError::PcOutOfRange { pc: 1, target: -4 })
+    );
+
+    // Landing right after the last instruction is a regular exit.
+    let mut machine = Machine::new(2);
+    assert_eq!(machine.run_checked(&parse("jmp +2\nhlt")), Ok(()));
+    assert_eq!(machine.pc(), 2);
+
+    let mut machine = Machine::new(2);
+    machine.set_reg(B, Word::MAX);
+    assert_eq!(
+        machine.run_checked(&parse("add a 1\nadd b 1")),
+        Err(VmError::RegisterOverflow { pc: 1, reg: B })
+    );
+    assert_eq!((machine.reg(B), machine.steps()), (Word::MAX, 1));
+
+    let mut machine = Machine::new(2).with_step_limit(7);
+    assert_eq!(
+        machine.run_checked(&parse("add a 1\njmp -1")),
+        Err(VmError::StepLimitExceeded(7))
+    );
+    assert_eq!(machine.reg(A), 4);
+
+    machine.set_step_limit(None);
+    assert_eq!(
+        machine.step_checked(&parse("add a 1\njmp -1")),
+        Ok(State::Running)
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    let prog = parse("set a 1\n\n  jnz a -1  \n");
+    assert_eq!(prog.len(), 2);
+    assert_eq!(prog[1], Toy::JumpIfNonZero(A, -1));
+
+    let err = Program::<Toy>::parse("set a 1\n\nfoo\nset 1 a").unwrap_err();
+    assert_eq!(err.line, 3);
+    assert_eq!(err.to_string(), "line 3: unknown instruction foo");
+
+    let err = Program::<Toy>::parse("set a 1\nset A 1").unwrap_err();
+    assert_eq!(err.line, 2);
+    assert_eq!(err.msg, "invalid register 'A'");
+}
+
+#[test]
+fn test_registers() {
+    assert_eq!(Reg::parse("a"), Ok(A));
+    assert_eq!(Reg::parse("b,"), Ok(B));
+    assert!(Reg::parse("ab").is_err());
+    assert!(Reg::parse("").is_err());
+    assert_eq!(Reg(25).name(), 'z');
+
+    let mut regs = Registers::new(2);
+    regs[B] = -7;
+
+    assert_eq!(regs.len(), 2);
+    assert_eq!(regs.get(B), Some(-7));
+    assert_eq!(regs.get(Reg(2)), None);
+    assert!(Registers::new(0).is_empty());
+}
+
+#[test]
+#[should_panic]
+fn test_register_out_of_bounds() {
+    let mut machine = Machine::new(1);
+    machine.run(&parse("add b 1"));
+}
+
+#[test]
+fn test_cfg() {
+    // b0: 0 | b1: 1..3, loops on itself | b2: 3, halts | b3: 4, unreachable
+    let prog = parse("set a 3\nadd a -1\njnz a -1\nhlt\nset b 1");
+    let cfg = Cfg::new(&prog);
+    let starts = cfg
+        .blocks()
+        .iter()
+        .map(|block| block.start)
+        .collect::<Vec<_>>();
+
+    assert_eq!(starts, [0, 1, 3, 4]);
+    assert_eq!(cfg.block_at(2), Some(1));
+    assert_eq!(cfg.block_at(5), None);
+    assert_eq!(
+        cfg.blocks()[1].succs,
+        [
+            (Target::Block(1), EdgeKind::Taken),
+            (Target::Block(2), EdgeKind::Fallthrough)
+        ]
+    );
+    assert_eq!(
+        cfg.blocks()[2].succs,
+        [(Target::Exit, EdgeKind::Fallthrough)]
+    );
+
+    let doms = cfg.dominators();
+    assert_eq!(doms[0], [true, false, false, false]);
+    assert_eq!(doms[1], [true, true, false, false]);
+    assert_eq!(doms[2], [true, true, true, false]);
+    assert_eq!(doms[3], [false; 4]);
+
+    assert_eq!(
+        cfg.loops(),
+        [Loop {
+            header: 1,
+            blocks: vec![1]
+        }]
+    );
+}
+
+#[test]
+fn test_dominators() {
+    // A diamond: the join is only dominated by the entry, and no edge closes a loop.
+    let prog = parse("jnz a +3\nset b 1\njmp +2\nset b 2\nhlt");
+    let cfg = Cfg::new(&prog);
+
+    assert_eq!(cfg.blocks().len(), 4);
+
+    let doms = cfg.dominators();
+    assert_eq!(doms[1], [true, true, false, false]);
+    assert_eq!(doms[2], [true, false, true, false]);
+    assert_eq!(doms[3], [true, false, false, true]);
+    assert!(cfg.loops().is_empty());
+
+    // Two back edges to the same header make a single loop.
+    let prog = parse("add a 1\njnz b -1\njnz a -2");
+    let loops = Cfg::new(&prog).loops();
+
+    assert_eq!(loops.len(), 1);
+    assert_eq!(loops[0].blocks, [0, 1]);
+}
@@ -1,44 +1,111 @@
-use itertools::Itertools;
+const EGGNOG_QUANTITY: usize = 150;
 
 /* ---------- */
 
-const EGGNOG_QUANTITY: usize = 150;
+/// Number of container combinations holding exactly the target quantity, indexed by the number
+/// of containers used.
+#[derive(Debug)]
+struct Counts(Vec<usize>);
 
-/* ---------- */
+impl Counts {
+    /// Knapsack-like table over (capacity, containers used): each container either extends the
+    /// combinations filling `capacity - size` or stays out of them.
+    fn new(containers: &[usize], target: usize) -> Self {
+        let mut ways = vec![vec![0usize; containers.len() + 1]; target + 1];
+
+        ways[0][0] = 1;
+
+        for (i, &size) in containers.iter().enumerate() {
+            for capacity in (size..=target).rev() {
+                for used in (1..=i + 1).rev() {
+                    ways[capacity][used] += ways[capacity - size][used - 1];
+                }
+            }
+        }
+
+        Self(ways.swap_remove(target))
+    }
 
-fn part1(containers: &[usize]) -> usize {
-    containers
-        .iter()
-        .powerset()
-        .filter(|set| set.iter().copied().sum::<usize>() == EGGNOG_QUANTITY)
-        .count()
+    #[inline]
+    fn total(&self) -> usize {
+        self.0.iter().sum()
+    }
+
+    #[inline]
+    fn with_fewest_containers(&self) -> usize {
+        self.0.iter().copied().find(|&ways| ways > 0).unwrap_or(0)
+    }
 }
 
 /* ---------- */
 
-fn part2(containers: &[usize]) -> usize {
-    let mut min = usize::MAX;
-    let mut count = 1usize;
+/// Lazily walks the container combinations holding exactly the target quantity. Combinations are
+/// given as container indices, so containers of the same size stay distinct.
+struct Combinations<'a> {
+    containers: &'a [usize],
+    /// Capacity of all the containers from a given index on, to drop hopeless branches early.
+    remaining_capacity: Vec<usize>,
+    stack: Vec<(usize, usize, Vec<usize>)>,
+}
 
+impl<'a> Combinations<'a> {
+    fn new(containers: &'a [usize], target: usize) -> Self {
+        let mut remaining_capacity = vec![0; containers.len() + 1];
 
-    containers
-        .iter()
-        .powerset()
-        .filter(|set| set.iter().copied().sum::<usize>() == EGGNOG_QUANTITY)
-        .for_each(|set| {
-            let amount = set.len();
+        for (i, &size) in containers.iter().enumerate().rev() {
+            remaining_capacity[i] = remaining_capacity[i + 1] + size;
+        }
 
-            match amount {
-                _ if amount < min => {
-                    count = 1;
-                    min = amount;
+        Self {
+            containers,
+            remaining_capacity,
+            stack: vec![(0, target, vec![])],
+        }
+    }
+}
+
+impl Iterator for Combinations<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((idx, missing, picked)) = self.stack.pop() {
+            if idx == self.containers.len() {
+                if missing == 0 {
+                    return Some(picked);
                 }
-                _ if amount == min => count += 1,
-                _ => ()
+                continue;
+            }
+
+            if self.remaining_capacity[idx] < missing {
+                continue;
+            }
+
+            let size = self.containers[idx];
+
+            self.stack.push((idx + 1, missing, picked.clone()));
+
+            if size <= missing {
+                let mut picked = picked;
+
+                picked.push(idx);
+                self.stack.push((idx + 1, missing - size, picked));
             }
-        });
+        }
+
+        None
+    }
+}
+
+/* ---------- */
 
-    count
+fn part1(counts: &Counts) -> usize {
+    counts.total()
+}
+
+/* ---------- */
+
+fn part2(counts: &Counts) -> usize {
+    counts.with_fewest_containers()
 }
 
 /* ---------- */
@@ -49,5 +116,44 @@ fn main() {
         .map(|line| line.parse().expect("failed to parse container size"))
         .collect::<_>();
 
-    utils::answer!(&containers);
+    match std::env::args().nth(1).as_deref() {
+        Some("list") => Combinations::new(&containers, EGGNOG_QUANTITY).for_each(|comb| {
+            let sizes = comb.iter().map(|&idx| containers[idx]).collect::<Vec<_>>();
+            println!("{comb:?} => {sizes:?}");
+        }),
+        _ => {
+            let counts = Counts::new(&containers, EGGNOG_QUANTITY);
+
+            utils::answer!(&counts);
+        }
+    }
+}
+
+/* ---------- */
+
+#[cfg(test)]
+mod test {
+    use crate::{Combinations, Counts};
+
+    const CONTAINERS: [usize; 5] = [20, 15, 10, 5, 5];
+
+    #[test]
+    fn test_counts() {
+        let counts = Counts::new(&CONTAINERS, 25);
+
+        assert_eq!(counts.total(), 4);
+        assert_eq!(counts.with_fewest_containers(), 3);
+    }
+
+    #[test]
+    fn test_combinations() {
+        let combs = Combinations::new(&CONTAINERS, 25).collect::<Vec<_>>();
+
+        assert_eq!(combs.len(), 4);
+        assert!(combs.contains(&vec![1, 2]));
+        assert!(combs.contains(&vec![0, 3]));
+        assert!(combs.contains(&vec![0, 4]));
+        assert!(combs.contains(&vec![1, 3, 4]));
+        assert_eq!(Combinations::new(&CONTAINERS, 100).count(), 0);
+    }
 }
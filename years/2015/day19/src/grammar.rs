@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::Transform;
+
+/* ---------- */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrammarError {
+    InvalidElement {
+        index: usize,
+        found: char,
+    },
+    UnknownElement(String),
+    /// A rule whose left side isn't a single element, or whose right side is empty.
+    InvalidRule {
+        from: String,
+        to: String,
+    },
+    Underivable,
+}
+
+impl Display for GrammarError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::InvalidElement { index, found } => {
+                write!(f, "invalid element start '{found}' at index {index}")
+            }
+            Self::UnknownElement(elem) => write!(f, "unknown element {elem}"),
+            Self::InvalidRule { from, to } => {
+                write!(
+                    f,
+                    "invalid rule '{from} => {to}', expected an element turning into some"
+                )
+            }
+            Self::Underivable => write!(f, "molecule can't be derived from the start symbol"),
+        }
+    }
+}
+
+/* ---------- */
+
+/// Splits a molecule into its elements: an uppercase letter followed by an optional lowercase
+/// one, or a lone lowercase letter for symbols such as the electron.
+pub fn tokenize(mol: &str) -> Result<Vec<&str>, GrammarError> {
+    let bytes = mol.as_bytes();
+    let mut elements = Vec::new();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let len = match bytes[index] {
+            b'A'..=b'Z' if bytes.get(index + 1).is_some_and(u8::is_ascii_lowercase) => 2,
+            b'A'..=b'Z' | b'a'..=b'z' => 1,
+            found => {
+                return Err(GrammarError::InvalidElement {
+                    index,
+                    found: found as char,
+                })
+            }
+        };
+
+        elements.push(&mol[index..index + len]);
+        index += len;
+    }
+
+    Ok(elements)
+}
+
+/* ---------- */
+
+type Symbol = usize;
+
+#[derive(Debug, Clone, Copy)]
+struct Binary {
+    lhs: Symbol,
+    left: Symbol,
+    right: Symbol,
+    cost: u32,
+}
+
+/// The replacement rules seen as a context free grammar over elements. Every rule is
+/// binarized so that molecules can be parsed with a weighted CYK: the intermediate symbols
+/// introduced by the binarization cost nothing, only the top-level production counts as a step.
+#[derive(Debug, Default)]
+pub struct Grammar {
+    symbols: HashMap<String, Symbol>,
+    symbol_count: usize,
+    units: Vec<(Symbol, Symbol)>,
+    binaries: Vec<Binary>,
+}
+
+impl Grammar {
+    pub fn new(transforms: &[Transform]) -> Result<Self, GrammarError> {
+        let mut grammar = Self::default();
+
+        for trfrm in transforms {
+            let invalid = || GrammarError::InvalidRule {
+                from: trfrm.from().to_string(),
+                to: trfrm.to().to_string(),
+            };
+
+            let lhs = match tokenize(trfrm.from())?.as_slice() {
+                [elem] => grammar.intern(elem),
+                _ => return Err(invalid()),
+            };
+            let rhs = tokenize(trfrm.to())?
+                .into_iter()
+                .map(|elem| grammar.intern(elem))
+                .collect::<Vec<_>>();
+
+            match rhs.as_slice() {
+                [] => return Err(invalid()),
+                [sym] => grammar.units.push((lhs, *sym)),
+                [first, rest @ .., last] => {
+                    let left = rest.iter().fold(*first, |left, &right| {
+                        let lhs = grammar.fresh();
+
+                        grammar.binaries.push(Binary {
+                            lhs,
+                            left,
+                            right,
+                            cost: 0,
+                        });
+                        lhs
+                    });
+
+                    grammar.binaries.push(Binary {
+                        lhs,
+                        left,
+                        right: *last,
+                        cost: 1,
+                    });
+                }
+            }
+        }
+
+        Ok(grammar)
+    }
+
+    fn intern(&mut self, elem: &str) -> Symbol {
+        if let Some(&sym) = self.symbols.get(elem) {
+            return sym;
+        }
+
+        let sym = self.fresh();
+        self.symbols.insert(elem.to_string(), sym);
+        sym
+    }
+
+    #[inline]
+    fn fresh(&mut self) -> Symbol {
+        self.symbol_count += 1;
+        self.symbol_count - 1
+    }
+
+    /// Relaxes the unit productions of a cell until nothing improves. Every unit rule costs one
+    /// step so this settles in at most one pass per symbol.
+    fn close_units(&self, cell: &mut [Option<u32>]) {
+        for _ in 0..self.symbol_count {
+            let mut changed = false;
+
+            for &(lhs, rhs) in &self.units {
+                let Some(cost) = cell[rhs].map(|cost| cost + 1) else {
+                    continue;
+                };
+
+                if cell[lhs].is_none_or(|old| cost < old) {
+                    cell[lhs] = Some(cost);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    /// Fewest replacements needed to go from `start` to `mol`.
+    pub fn min_steps(&self, start: &str, mol: &str) -> Result<usize, GrammarError> {
+        let start = *self
+            .symbols
+            .get(start)
+            .ok_or_else(|| GrammarError::UnknownElement(start.to_string()))?;
+        let tokens = tokenize(mol)?
+            .into_iter()
+            .map(|elem| {
+                self.symbols
+                    .get(elem)
+                    .copied()
+                    .ok_or_else(|| GrammarError::UnknownElement(elem.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let n = tokens.len();
+
+        if n == 0 {
+            return Err(GrammarError::Underivable);
+        }
+
+        // `table[len - 1][i]` holds the cheapest derivation of `tokens[i..i + len]` per symbol.
+        let mut table: Vec<Vec<Vec<Option<u32>>>> = Vec::with_capacity(n);
+
+        table.push(
+            tokens
+                .iter()
+                .map(|&tok| {
+                    let mut cell = vec![None; self.symbol_count];
+
+                    cell[tok] = Some(0);
+                    self.close_units(&mut cell);
+                    cell
+                })
+                .collect(),
+        );
+
+        for len in 2..=n {
+            let row = (0..=n - len)
+                .map(|i| {
+                    let mut cell = vec![None; self.symbol_count];
+
+                    for split in 1..len {
+                        let left = &table[split - 1][i];
+                        let right = &table[len - split - 1][i + split];
+
+                        for rule in &self.binaries {
+                            let (Some(l), Some(r)) = (left[rule.left], right[rule.right]) else {
+                                continue;
+                            };
+                            let cost = l + r + rule.cost;
+
+                            if cell[rule.lhs].is_none_or(|old| cost < old) {
+                                cell[rule.lhs] = Some(cost);
+                            }
+                        }
+                    }
+
+                    self.close_units(&mut cell);
+                    cell
+                })
+                .collect();
+
+            table.push(row);
+        }
+
+        table[n - 1][0][start]
+            .map(|cost| cost as usize)
+            .ok_or(GrammarError::Underivable)
+    }
+}
@@ -1,4 +1,17 @@
-use std::collections::HashSet;
+mod grammar;
+mod rolling;
+
+use std::collections::HashMap;
+
+use grammar::Grammar;
+use rolling::PrefixHash;
+
+/* ---------- */
+
+const ELECTRON: &str = "e";
+
+/// The greedy reduction gets stuck on some rule orders, give up after that many reshuffles.
+const MAX_RESTARTS: usize = 1_000;
 
 /* ---------- */
 
@@ -16,6 +29,11 @@ impl Transform {
     fn to(&self) -> &'static str {
         self.to
     }
+
+    #[inline]
+    fn is_electron(&self) -> bool {
+        self.from == ELECTRON
+    }
 }
 
 impl From<&'static str> for Transform {
@@ -35,6 +53,54 @@ impl From<&'static str> for Transform {
 
 /* ---------- */
 
+/// Minimal xorshift generator, only used to reshuffle the rule order between reduction attempts.
+struct Shuffler(u64);
+
+impl Shuffler {
+    #[inline]
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/* ---------- */
+
+/// A molecule with the window `start..end` replaced by `with`, without building the result.
+#[derive(Debug)]
+struct Splice<'a> {
+    start: usize,
+    end: usize,
+    with: &'a [u8],
+}
+
+impl Splice<'_> {
+    #[inline]
+    fn bytes<'a>(&'a self, mol: &'a [u8]) -> impl Iterator<Item = &'a u8> {
+        mol[..self.start]
+            .iter()
+            .chain(self.with)
+            .chain(&mol[self.end..])
+    }
+
+    /// Whether both splices of `mol` give the same molecule.
+    #[inline]
+    fn same_as(&self, other: &Self, mol: &[u8]) -> bool {
+        self.bytes(mol).eq(other.bytes(mol))
+    }
+}
+
+/* ---------- */
+
 #[derive(Debug, Default, Clone)]
 struct Molecule(String);
 
@@ -44,17 +110,85 @@ impl Molecule {
         self.0.is_empty()
     }
 
-    fn replacements(&self, transform: Transform) -> Vec<String> {
-        let needle_len = transform.from().len();
+    /// Counts the distinct molecules one replacement away. Each candidate is keyed by its length
+    /// and the hash of the spliced string, which only needs the replaced window, so no successor
+    /// is materialized. Candidates sharing a key are compared byte by byte before being merged,
+    /// so hash collisions can't skew the count.
+    fn distinct_successors(&self, transforms: &[Transform]) -> usize {
+        let bytes = self.0.as_bytes();
+        let prefix = PrefixHash::new(bytes);
+        let mut successors = HashMap::<_, Vec<Splice>>::new();
+        let mut count = 0;
 
-        self.0
-            .match_indices(transform.from())
-            .map(|(index, _)| {
-                let mut new = self.0.clone();
-                new.replace_range(index..needle_len + index, transform.to());
-                new
-            })
-            .collect::<Vec<String>>()
+        transforms.iter().for_each(|transform| {
+            let (from, to) = (transform.from().as_bytes(), transform.to().as_bytes());
+            let to_hash = rolling::hash(to);
+
+            // Every start position is tried, matches may overlap.
+            (0..bytes.len())
+                .filter(|&index| bytes[index..].starts_with(from))
+                .for_each(|index| {
+                    let splice = Splice {
+                        start: index,
+                        end: index + from.len(),
+                        with: to,
+                    };
+                    let hash = prefix.splice(splice.start, splice.end, to_hash, to.len());
+                    let same_key = successors
+                        .entry((bytes.len() - from.len() + to.len(), hash))
+                        .or_default();
+
+                    if !same_key.iter().any(|other| other.same_as(&splice, bytes)) {
+                        same_key.push(splice);
+                        count += 1;
+                    }
+                });
+        });
+
+        count
+    }
+
+    /// Walks the transforms backwards from the molecule down to a single electron, always
+    /// collapsing the first rule that still matches. When no rule applies anymore the rule order
+    /// is reshuffled and the reduction starts over.
+    ///
+    /// Rules that don't shrink the molecule, such as `H => O` and `O => H`, could keep an
+    /// attempt going forever, so each attempt is given up after a number of steps: every element
+    /// collapses at most once, with at most one step per rule in between.
+    fn reduce(&self, transforms: &[Transform]) -> Option<usize> {
+        let mut rules = transforms
+            .iter()
+            .filter(|trfrm| !trfrm.is_electron())
+            .collect::<Vec<_>>();
+        let mut shuffler = Shuffler(0x9e37_79b9_7f4a_7c15);
+        let max_steps = self.0.len() * (rules.len() + 1);
+
+        for _ in 0..MAX_RESTARTS {
+            let mut mol = self.0.clone();
+            let mut steps = 0;
+
+            while let Some(trfrm) = rules.iter().find(|trfrm| mol.contains(trfrm.to())) {
+                if steps == max_steps {
+                    break;
+                }
+
+                let index = mol.find(trfrm.to()).expect("a matching transform");
+
+                mol.replace_range(index..index + trfrm.to().len(), trfrm.from());
+                steps += 1;
+            }
+
+            if transforms
+                .iter()
+                .any(|trfrm| trfrm.is_electron() && trfrm.to() == mol)
+            {
+                return Some(steps + 1);
+            }
+
+            shuffler.shuffle(&mut rules);
+        }
+
+        None
     }
 }
 
@@ -72,25 +206,173 @@ impl From<String> for Molecule {
 
 /* ---------- */
 
-fn main() {
+fn parse(input: &'static str) -> (Vec<Transform>, Molecule) {
     let mut mol = Molecule::default();
-    let mut unique_replacements = HashSet::new();
+    let mut transforms = Vec::new();
 
-    utils::input_str!()
+    input
         .lines()
         .filter(|line| !line.is_empty())
-        .rev()
         .for_each(|line| {
-            if !line.contains(" => ") && mol.is_empty() {
+            if line.contains(" => ") {
+                transforms.push(line.into());
+            } else if mol.is_empty() {
                 mol = line.into();
-                return;
+            } else {
+                panic!("expected a single molecule")
             }
+        });
 
-            let transform: Transform = line.into();
-            let replacements = mol.replacements(transform);
+    (transforms, mol)
+}
 
-            unique_replacements.extend(replacements.into_iter());
-        });
+/* ---------- */
+
+fn part1((transforms, mol): &(Vec<Transform>, Molecule)) -> usize {
+    mol.distinct_successors(transforms)
+}
+
+/* ---------- */
+
+fn part2((transforms, mol): &(Vec<Transform>, Molecule)) -> usize {
+    Grammar::new(transforms)
+        .expect("a valid grammar")
+        .min_steps(ELECTRON, &mol.0)
+        .expect("a molecule reachable from an electron")
+}
+
+/* ---------- */
+
+fn bench((transforms, mol): &(Vec<Transform>, Molecule)) {
+    println!("[BENCH] greedy reverse reduction");
+    utils::bench!(std::hint::black_box(mol.reduce(transforms)));
+
+    println!("[BENCH] CYK parsing");
+    utils::bench!(std::hint::black_box(
+        Grammar::new(transforms)
+            .and_then(|grammar| grammar.min_steps(ELECTRON, &mol.0))
+            .ok()
+    ));
+}
+
+/* ---------- */
+
+fn main() {
+    let input = parse(utils::input_str!());
+
+    match std::env::args().nth(1).as_deref() {
+        Some("bench") => bench(&input),
+        _ => utils::answer!(&input),
+    }
+}
+
+/* ---------- */
+
+#[cfg(test)]
+mod test {
+    use crate::grammar::{tokenize, Grammar, GrammarError};
+    use std::collections::HashSet;
+
+    use crate::{parse, part1, part2, ELECTRON};
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(&parse("H => HO\nH => OH\nO => HH\n\nHOH")), 4);
+        assert_eq!(part1(&parse("H => HO\nH => OH\nO => HH\n\nHOHOHO")), 7);
+    }
+
+    #[test]
+    fn test_distinct_successors() {
+        // Builds every successor through the string splits, matches overlapping or not.
+        let naive = |input| {
+            let (transforms, mol) = parse(input);
+            let mut successors = HashSet::new();
+
+            for transform in &transforms {
+                for index in 0..mol.0.len() {
+                    let (head, tail) = mol.0.split_at(index);
+
+                    if let Some(rest) = tail.strip_prefix(transform.from()) {
+                        successors.insert(format!("{head}{}{rest}", transform.to()));
+                    }
+                }
+            }
+
+            (mol.distinct_successors(&transforms), successors.len())
+        };
+
+        let (fast, slow) = naive(utils::input_str!());
+        assert_eq!(fast, slow);
+
+        assert_eq!(naive("HH => X\n\nHHH"), (2, 2));
+        assert_eq!(naive("HH => X\nH => X\n\nHHH"), (5, 5));
+        assert_eq!(naive("H => HH\n\nHHHH"), (1, 1));
+    }
+
+    #[test]
+    fn test_part2() {
+        let hoh = "e => H\ne => O\nH => HO\nH => OH\nO => HH\n\nHOH";
+        let hohoho = "e => H\ne => O\nH => HO\nH => OH\nO => HH\n\nHOHOHO";
+
+        assert_eq!(part2(&parse(hoh)), 3);
+        assert_eq!(part2(&parse(hohoho)), 6);
+    }
 
-    println!("result = {}", unique_replacements.len())
+    #[test]
+    fn test_greedy_reduction() {
+        let (transforms, mol) = parse("e => H\ne => O\nH => HO\nH => OH\nO => HH\n\nHOHOHO");
+
+        assert_eq!(mol.reduce(&transforms), Some(6));
+
+        // Unit rules looping on each other don't keep the reduction from giving up.
+        let (transforms, mol) = parse("e => HH\nH => O\nO => H\n\nHO");
+        assert_eq!(mol.reduce(&transforms), None);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("CRnCaSiAr").unwrap(),
+            ["C", "Rn", "Ca", "Si", "Ar"]
+        );
+        assert_eq!(tokenize("e").unwrap(), ["e"]);
+        assert_eq!(
+            tokenize("Ca1").unwrap_err(),
+            GrammarError::InvalidElement {
+                index: 2,
+                found: '1'
+            }
+        );
+    }
+
+    #[test]
+    fn test_grammar() {
+        let (transforms, _) = parse("e => H\ne => O\nH => HO\nH => OH\nO => HH\nH => CRnHAr\n\nH");
+        let grammar = Grammar::new(&transforms).unwrap();
+
+        assert_eq!(grammar.min_steps(ELECTRON, "HOH"), Ok(3));
+        assert_eq!(grammar.min_steps(ELECTRON, "CRnOHAr"), Ok(3));
+        assert_eq!(grammar.min_steps(ELECTRON, "HH"), Ok(2));
+        assert_eq!(
+            grammar.min_steps(ELECTRON, "CRnAr"),
+            Err(GrammarError::Underivable)
+        );
+        assert_eq!(
+            grammar.min_steps(ELECTRON, "HMg"),
+            Err(GrammarError::UnknownElement("Mg".to_string()))
+        );
+
+        for rules in [
+            "e => H\nHO => X\n\nH",
+            "e => H\nH => \n\nH",
+            "e => H\n => O\n\nH",
+        ] {
+            let (transforms, _) = parse(rules);
+
+            assert!(matches!(
+                Grammar::new(&transforms),
+                Err(GrammarError::InvalidRule { .. })
+            ));
+        }
+    }
 }
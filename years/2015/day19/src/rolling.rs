@@ -0,0 +1,99 @@
+/// Mersenne prime modulus, large enough that collisions between successors are rare.
+const MODULUS: u64 = (1 << 61) - 1;
+const BASE: u64 = 131;
+
+/* ---------- */
+
+#[inline]
+fn mul(a: u64, b: u64) -> u64 {
+    let prod = a as u128 * b as u128;
+    let folded = (prod & MODULUS as u128) as u64 + (prod >> 61) as u64;
+
+    if folded >= MODULUS {
+        folded - MODULUS
+    } else {
+        folded
+    }
+}
+
+#[inline]
+fn add(a: u64, b: u64) -> u64 {
+    let sum = a + b;
+
+    if sum >= MODULUS {
+        sum - MODULUS
+    } else {
+        sum
+    }
+}
+
+#[inline]
+fn sub(a: u64, b: u64) -> u64 {
+    add(a, MODULUS - b)
+}
+
+pub fn hash(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0, |acc, &byte| add(mul(acc, BASE), byte as u64))
+}
+
+/* ---------- */
+
+/// Polynomial prefix hashes of a string, so that the hash of the string with any window swapped
+/// for another piece of text can be computed from the window alone.
+#[derive(Debug)]
+pub struct PrefixHash {
+    prefixes: Vec<u64>,
+    powers: Vec<u64>,
+}
+
+impl PrefixHash {
+    pub fn new(bytes: &[u8]) -> Self {
+        let mut prefixes = Vec::with_capacity(bytes.len() + 1);
+        let mut powers = Vec::with_capacity(bytes.len() + 1);
+
+        prefixes.push(0);
+        powers.push(1);
+
+        for &byte in bytes {
+            prefixes.push(add(mul(prefixes[prefixes.len() - 1], BASE), byte as u64));
+            powers.push(mul(powers[powers.len() - 1], BASE));
+        }
+
+        Self { prefixes, powers }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.prefixes.len() - 1
+    }
+
+    #[inline]
+    fn power(&self, exp: usize) -> u64 {
+        self.powers
+            .get(exp)
+            .copied()
+            .unwrap_or_else(|| (0..exp).fold(1, |acc, _| mul(acc, BASE)))
+    }
+
+    #[inline]
+    fn range(&self, start: usize, end: usize) -> u64 {
+        sub(
+            self.prefixes[end],
+            mul(self.prefixes[start], self.powers[end - start]),
+        )
+    }
+
+    /// Hash of the string once `start..end` is replaced by a text of length `with_len` and
+    /// hash `with_hash`.
+    pub fn splice(&self, start: usize, end: usize, with_hash: u64, with_len: usize) -> u64 {
+        let head = add(mul(self.prefixes[start], self.power(with_len)), with_hash);
+        let tail_len = self.len() - end;
+
+        add(
+            mul(head, self.powers[tail_len]),
+            self.range(end, self.len()),
+        )
+    }
+}
@@ -1,81 +1,116 @@
 const INPUT_RAW: u32 = 36000000;
-const MAX_HOUSE: u32 = 50;
+
+const NORMAL_ELVES: Delivery = Delivery {
+    mult: 10,
+    max_houses: None,
+};
+const LAZY_ELVES: Delivery = Delivery {
+    mult: 11,
+    max_houses: Some(50),
+};
 
 /* ---------- */
 
-const fn house_max_elf(n_house: u32) -> u32 {
-    (n_house - 1)  / MAX_HOUSE + 1
+/// How the elves deliver: elf `n` leaves `n * mult` presents at every house it visits, and
+/// stops after `max_houses` houses if there is a cap.
+#[derive(Debug, Clone, Copy)]
+struct Delivery {
+    mult: u32,
+    max_houses: Option<u32>,
 }
 
-/* ---------- */
+impl Delivery {
+    /// Elf `n` always visits house `n`, so house `target / mult` gets enough presents on its
+    /// own and no answer lies beyond it.
+    const fn bound(&self, target: u32) -> usize {
+        let bound = target.div_ceil(self.mult) as usize;
 
-fn find_elves(n: u32, elf_min: Option<u32>) -> Vec<u32> {
-    #[inline(always)]
-    fn max_range(n: u32) -> u32 {
-        ((n as f32).sqrt() + 1.0) as u32
+        if bound == 0 {
+            1
+        } else {
+            bound
+        }
     }
 
-    let mut ret = vec![];
-    let mut large_div = vec![];
-    let elf_min = elf_min.unwrap_or(1);
+    /// Sieves the deliveries elf by elf. Once elf `n` went by, every elf visiting house `n` did,
+    /// so the house can be checked right away.
+    fn first_house(&self, target: u32) -> u32 {
+        let bound = self.bound(target);
+        let mut presents = vec![0u64; bound + 1];
+
+        for elf in 1..=bound {
+            let last = match self.max_houses {
+                Some(max) => bound.min(elf * max as usize),
+                None => bound,
+            };
 
-    for i in 1..max_range(n) {
-        if n % i == 0 {
-            if i >= elf_min {
-                ret.push(i);
+            for house in (elf..=last).step_by(elf) {
+                presents[house] += elf as u64 * self.mult as u64;
             }
 
-            if i > 1 && i * i != n {
-                large_div.push(n / i)
+            if presents[elf] >= target as u64 {
+                return elf as u32;
             }
         }
-    }
-
-    ret.extend(large_div.iter().rev().skip_while(|&&inner| inner < elf_min));
 
-    if n != 1 {
-        ret.push(n);
+        unreachable!("house {bound} gets at least {target} presents")
     }
-
-    ret
 }
 
 /* ---------- */
 
-#[inline(always)]
-fn nb_presents(house_id: u32, lazy_elves: bool, mult: u32) -> u32 {
-    let min_elf = lazy_elves.then(|| house_max_elf(house_id));
-    let houses = find_elves(house_id, min_elf);
-
-    houses.iter().sum::<u32>() * mult
+fn part1() -> u32 {
+    NORMAL_ELVES.first_house(INPUT_RAW)
 }
 
 /* ---------- */
 
-fn part1() -> u32 {
-    let mut house_id = 1u32;
+fn part2() -> u32 {
+    LAZY_ELVES.first_house(INPUT_RAW)
+}
 
-    while nb_presents(house_id, false, 10) < INPUT_RAW {
-        house_id += 1;
-    }
+/* ---------- */
 
-    house_id
+fn main() {
+    utils::answer!()
 }
 
 /* ---------- */
 
-fn part2() -> u32 {
-    let mut house_id = 1u32;
+#[cfg(test)]
+mod test {
+    use crate::{Delivery, LAZY_ELVES, NORMAL_ELVES};
 
-    while nb_presents(house_id, true, 11) < INPUT_RAW {
-        house_id += 1;
+    fn naive_presents(delivery: &Delivery, house: u32) -> u64 {
+        (1..=house)
+            .filter(|elf| house.is_multiple_of(*elf))
+            .filter(|elf| delivery.max_houses.is_none_or(|max| house / elf <= max))
+            .map(|elf| elf as u64 * delivery.mult as u64)
+            .sum()
     }
 
-    house_id
-}
+    #[test]
+    fn test_examples() {
+        assert_eq!(NORMAL_ELVES.first_house(70), 4);
+        assert_eq!(NORMAL_ELVES.first_house(100), 6);
+        assert_eq!(NORMAL_ELVES.first_house(130), 8);
+        assert_eq!(NORMAL_ELVES.first_house(0), 1);
+    }
 
-/* ---------- */
+    #[test]
+    fn test_against_naive() {
+        let capped = Delivery {
+            mult: 3,
+            max_houses: Some(2),
+        };
 
-fn main() {
-    utils::answer!()
+        for delivery in [NORMAL_ELVES, LAZY_ELVES, capped] {
+            for target in (1..5_000).step_by(37) {
+                let house = delivery.first_house(target);
+
+                assert!(naive_presents(&delivery, house) >= target as u64);
+                assert!((1..house).all(|h| naive_presents(&delivery, h) < target as u64));
+            }
+        }
+    }
 }
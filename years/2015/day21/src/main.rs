@@ -4,7 +4,28 @@ use serde::Deserialize;
 
 /* ---------- */
 
-const BOSS: Entity = Entity::new(100, 8, 2);
+#[derive(Debug)]
+enum Error {
+    Json(serde_json::Error),
+    BadStat(String),
+    MissingStat(&'static str),
+    DuplicateItem(String),
+    NoWeapon,
+    StatsOverflow,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "invalid shop : {err}"),
+            Self::BadStat(line) => write!(f, "bad stat line : {line}"),
+            Self::MissingStat(name) => write!(f, "missing stat : {name}"),
+            Self::DuplicateItem(name) => write!(f, "item sold twice : {name}"),
+            Self::NoWeapon => write!(f, "the shop doesn't sell any weapon"),
+            Self::StatsOverflow => write!(f, "equipped stats don't fit in a u8"),
+        }
+    }
+}
 
 /* ---------- */
 
@@ -20,25 +41,48 @@ impl Stats {
         Self { hp, damage, armor }
     }
 
-    const fn resistance(&self, dmg: u8) -> u8 {
-        let max_hits = self.hp / (dmg - self.armor);
+    /// Parses stats in the puzzle format, one `Name: value` line per stat.
+    fn parse(text: &str) -> Result<Self, Error> {
+        let (mut hp, mut damage, mut armor) = (None, None, None);
+
+        for line in text.lines().filter(|line| !line.is_empty()) {
+            let bad_stat = || Error::BadStat(line.to_string());
+            let (name, value) = line.split_once(": ").ok_or_else(bad_stat)?;
+            let value = value.trim().parse::<u8>().map_err(|_| bad_stat())?;
+
+            let stat = match name {
+                "Hit Points" => &mut hp,
+                "Damage" => &mut damage,
+                "Armor" => &mut armor,
+                _ => return Err(bad_stat()),
+            };
 
-        match self.hp % dmg {
-            0 => max_hits,
-            _ => max_hits + 1,
+            if stat.replace(value).is_some() {
+                return Err(bad_stat());
+            }
         }
-    }
 
-    const fn total_dmg_relative_stats(&self) -> u8 {
-        self.damage + self.armor
+        Ok(Self {
+            hp: hp.ok_or(Error::MissingStat("Hit Points"))?,
+            damage: damage.ok_or(Error::MissingStat("Damage"))?,
+            armor: armor.ok_or(Error::MissingStat("Armor"))?,
+        })
     }
 
-    const fn dmg_required(&self, turns: u8) -> u8 {
-        let dmg = self.hp / turns;
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(Self {
+            hp: self.hp.checked_add(other.hp)?,
+            damage: self.damage.checked_add(other.damage)?,
+            armor: self.armor.checked_add(other.armor)?,
+        })
+    }
 
-        match self.hp % turns {
-            0 => dmg,
-            _ => dmg + 1,
+    /// Damage dealt by a hit from an attacker with `dmg` damage, never less than one.
+    const fn hit_taken(&self, dmg: u8) -> u8 {
+        if dmg > self.armor {
+            dmg - self.armor
+        } else {
+            1
         }
     }
 }
@@ -46,30 +90,22 @@ impl Stats {
 impl Add<Self> for Stats {
     type Output = Self;
     fn add(self, other: Self) -> Self {
-        Self {
-            hp: self.hp + other.hp,
-            damage: self.damage + other.damage,
-            armor: self.armor + other.armor,
-        }
+        self.checked_add(&other)
+            .expect("stats validated against overflow")
     }
 }
 
 impl Add<&Self> for Stats {
     type Output = Self;
     fn add(self, other: &Self) -> Self {
-        Self {
-            hp: self.hp + other.hp,
-            damage: self.damage + other.damage,
-            armor: self.armor + other.armor,
-        }
+        self.checked_add(other)
+            .expect("stats validated against overflow")
     }
 }
 
 impl AddAssign<Self> for Stats {
     fn add_assign(&mut self, other: Self) {
-        self.hp += other.hp;
-        self.damage += other.damage;
-        self.armor += other.armor;
+        *self = *self + other;
     }
 }
 
@@ -111,37 +147,30 @@ impl std::fmt::Display for Item {
 
 /* ---------- */
 
-struct InventoryStats {
+struct InventoryStats<'a> {
+    items: Vec<&'a Item>,
     cost: u16,
     stats: Stats,
 }
 
-impl InventoryStats {
+impl<'a> InventoryStats<'a> {
     fn new(
-        weapon: &Item,
-        armor: Option<&Item>,
-        rring: Option<&Item>,
-        lring: Option<&Item>,
+        weapon: &'a Item,
+        armor: Option<&'a Item>,
+        rring: Option<&'a Item>,
+        lring: Option<&'a Item>,
     ) -> Self {
+        let mut items = vec![weapon];
         let mut stats = weapon.stats();
         let mut cost = weapon.cost as u16;
 
-        if let Some(armor) = armor {
-            stats += armor.stats();
-            cost += armor.cost as u16
+        for item in [armor, rring, lring].into_iter().flatten() {
+            items.push(item);
+            stats += item.stats();
+            cost += item.cost as u16;
         }
 
-        if let Some(ring) = lring {
-            stats += ring.stats();
-            cost += ring.cost as u16
-        }
-
-        if let Some(ring) = rring {
-            stats += ring.stats();
-            cost += ring.cost as u16
-        }
-
-        Self { cost, stats }
+        Self { items, cost, stats }
     }
 
     fn stats(&self) -> &Stats {
@@ -149,58 +178,73 @@ impl InventoryStats {
     }
 
     fn cost(&self) -> u16 {
-        self.cost as u16
+        self.cost
     }
 }
 
-impl std::fmt::Debug for InventoryStats {
+impl std::fmt::Debug for InventoryStats<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "cost: {}, stats: {}",
-            self.cost,
-            self.stats.total_dmg_relative_stats()
-        )
+        write!(f, "cost: {}, stats: {:?}", self.cost, self.stats)
+    }
+}
+
+impl std::fmt::Display for InventoryStats<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let names = self
+            .items
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect::<Vec<_>>();
+
+        write!(f, "{} ({})", self.cost, names.join(", "))
     }
 }
 
 /* ---------- */
 
-struct Entity {
+struct Entity<'a> {
     base_stats: Stats,
-    inventory: Option<InventoryStats>,
+    inventory: Option<&'a InventoryStats<'a>>,
 }
 
-impl Entity {
-    const fn new(hp: u8, damage: u8, armor: u8) -> Self {
+impl<'a> Entity<'a> {
+    const fn new(base_stats: Stats) -> Self {
         Self {
-            base_stats: Stats::new(hp, damage, armor),
+            base_stats,
             inventory: None,
         }
     }
 
-    fn resistance(&self, dmg: u8) -> u8 {
-        match &self.inventory {
-            Some(inv) => (self.base_stats + inv.stats()).resistance(dmg),
-            None => self.base_stats.resistance(dmg),
+    fn equip(&self, inventory: &'a InventoryStats<'a>) -> Self {
+        Self {
+            base_stats: self.base_stats,
+            inventory: Some(inventory),
         }
     }
 
-    fn stats_required_to_kill_in_turns(&self, turns: u8) -> u8 {
-        match &self.inventory {
-            Some(inv) => (self.base_stats + inv.stats()).dmg_required(turns),
-            None => self.base_stats.dmg_required(turns),
+    fn stats(&self) -> Stats {
+        match self.inventory {
+            Some(inv) => self.base_stats + inv.stats(),
+            None => self.base_stats,
         }
     }
 
-    fn damage(&self) -> u8 {
-        let mut dmg = self.base_stats.damage;
+    /// Plays the fight turn by turn, `self` striking first. Returns whether `self` wins.
+    fn beats(&self, other: &Entity) -> bool {
+        let (attacker, defender) = (self.stats(), other.stats());
+        let (mut attacker_hp, mut defender_hp) = (attacker.hp, defender.hp);
 
-        if let Some(inv) = &self.inventory {
-            dmg += inv.stats().damage
-        }
+        loop {
+            defender_hp = defender_hp.saturating_sub(defender.hit_taken(attacker.damage));
+            if defender_hp == 0 {
+                return true;
+            }
 
-        dmg
+            attacker_hp = attacker_hp.saturating_sub(attacker.hit_taken(defender.damage));
+            if attacker_hp == 0 {
+                return false;
+            }
+        }
     }
 }
 
@@ -214,69 +258,190 @@ struct Shop {
 }
 
 impl Shop {
-    fn generate_all_inv_combinaisons_with_higher_stats(&self, stats: u8) -> Vec<InventoryStats> {
-        let mut combinaisons = vec![];
+    fn load(json: &str) -> Result<Self, Error> {
+        let shop: Self = serde_json::from_str(json).map_err(Error::Json)?;
+        let mut names = std::collections::HashSet::new();
 
-        let rings_loop = |weap: &Item, armor: Option<&Item>, combs: &mut Vec<InventoryStats>| {
-            for r in 0..self.rings.len() {
-                for l in 0..self.rings.len() {
-                    if l == r {
-                        continue;
-                    }
-
-                    let inv_stats = InventoryStats::new(
-                        weap,
-                        armor,
-                        Some(&self.rings[r]),
-                        Some(&self.rings[l]),
-                    );
-                    if stats <= inv_stats.stats().total_dmg_relative_stats() {
-                        combs.push(inv_stats)
-                    }
-                }
-            }
+        if shop.weapons.is_empty() {
+            return Err(Error::NoWeapon);
+        }
 
-            for ring in &self.rings {
-                let inv_stats = InventoryStats::new(weap, armor, Some(ring), None);
-                if stats <= inv_stats.stats().total_dmg_relative_stats() {
-                    combs.push(inv_stats)
-                }
+        for item in shop.weapons.iter().chain(&shop.armor).chain(&shop.rings) {
+            if !names.insert(item.name.as_str()) {
+                return Err(Error::DuplicateItem(item.name.clone()));
             }
+        }
+
+        shop.richest().ok_or(Error::StatsOverflow)?;
+        Ok(shop)
+    }
+
+    /// Upper bound of every loadout stat: the best weapon, armor and pair of rings for each stat
+    /// on its own. `None` if that bound overflows.
+    fn richest(&self) -> Option<Stats> {
+        let best = |items: &[Item], count: usize, stat: fn(&Item) -> u8| {
+            let mut values = items.iter().map(stat).collect::<Vec<_>>();
+
+            values.sort_unstable_by(|a, b| b.cmp(a));
+            values
+                .into_iter()
+                .take(count)
+                .try_fold(0u8, u8::checked_add)
+        };
+        let total = |stat: fn(&Item) -> u8| {
+            best(&self.weapons, 1, stat)?
+                .checked_add(best(&self.armor, 1, stat)?)?
+                .checked_add(best(&self.rings, 2, stat)?)
         };
 
-        for weap in &self.weapons {
-            for armor in &self.armor {
-                rings_loop(weap, Some(armor), &mut combinaisons);
+        Some(Stats::new(
+            0,
+            total(|item| item.damage)?,
+            total(|item| item.armor)?,
+        ))
+    }
+
+    /// Every legal loadout: one weapon, at most one armor and at most two distinct rings.
+    fn loadouts(&self) -> Vec<InventoryStats<'_>> {
+        let mut rings = vec![(None, None)];
+        let mut combinaisons = vec![];
+
+        for (r, rring) in self.rings.iter().enumerate() {
+            rings.push((Some(rring), None));
+            for lring in &self.rings[r + 1..] {
+                rings.push((Some(rring), Some(lring)));
             }
-            rings_loop(weap, None, &mut combinaisons);
+        }
 
-            let inv_stats = InventoryStats::new(weap, None, None, None);
-            if stats <= inv_stats.stats().total_dmg_relative_stats() {
-                combinaisons.push(inv_stats)
+        for weap in &self.weapons {
+            for armor in std::iter::once(None).chain(self.armor.iter().map(Some)) {
+                for &(rring, lring) in &rings {
+                    combinaisons.push(InventoryStats::new(weap, armor, rring, lring));
+                }
             }
         }
 
         combinaisons
     }
 
-    fn find_cheapest_stuff_cost(&self, stats: u8) -> u16 {
-        let combs = self.generate_all_inv_combinaisons_with_higher_stats(stats);
-
-        combs
-            .iter()
-            .map(|inv_stats| inv_stats.cost())
-            .min()
-            .expect("expected a valid stuff cost")
+    /// Splits the loadouts between the ones winning against `boss` and the ones losing.
+    fn fights(
+        &self,
+        player: &Stats,
+        boss: &Stats,
+    ) -> Result<(Vec<InventoryStats<'_>>, Vec<InventoryStats<'_>>), Error> {
+        player
+            .checked_add(&self.richest().ok_or(Error::StatsOverflow)?)
+            .ok_or(Error::StatsOverflow)?;
+
+        let (player, boss) = (Entity::new(*player), Entity::new(*boss));
+
+        Ok(self
+            .loadouts()
+            .into_iter()
+            .partition(|inv| player.equip(inv).beats(&boss)))
     }
 }
 
 /* ---------- */
 
+fn part1((shop, player, boss): &(Shop, Stats, Stats)) -> InventoryStats<'_> {
+    let (wins, _) = shop.fights(player, boss).expect("stats fitting in a u8");
+
+    wins.into_iter()
+        .min_by_key(|inv| inv.cost())
+        .expect("a winning loadout")
+}
+
+/* ---------- */
+
+fn part2((shop, player, boss): &(Shop, Stats, Stats)) -> InventoryStats<'_> {
+    let (_, losses) = shop.fights(player, boss).expect("stats fitting in a u8");
+
+    losses
+        .into_iter()
+        .max_by_key(|inv| inv.cost())
+        .expect("a losing loadout")
+}
+
+/* ---------- */
+
 fn main() {
-    let shop: Shop = serde_json::from_str(utils::input_str!("input.json")).unwrap();
-    let player = Entity::new(100, 0, 0);
-    let turns_to_survive = player.resistance(BOSS.damage());
-    let stats_req = BOSS.stats_required_to_kill_in_turns(turns_to_survive);
+    let shop = Shop::load(utils::input_str!("input.json")).expect("a valid shop");
+    let boss = Stats::parse(utils::input_str!("boss.txt")).expect("valid boss stats");
+    let player = match std::env::args().nth(1) {
+        Some(path) => Stats::parse(&std::fs::read_to_string(path).expect("a readable file")),
+        None => Stats::parse(utils::input_str!("player.txt")),
+    }
+    .expect("valid player stats");
+    let input = (shop, player, boss);
+
+    utils::answer!(&input);
+}
+
+/* ---------- */
+
+#[cfg(test)]
+mod test {
+    use crate::{Entity, Error, Shop, Stats};
 
-    println!("res = {}", shop.find_cheapest_stuff_cost(stats_req));
+    #[test]
+    fn test_fight() {
+        let player = Entity::new(Stats::new(8, 5, 5));
+        let boss = Entity::new(Stats::new(12, 7, 2));
+
+        assert!(player.beats(&boss));
+        assert!(!Entity::new(Stats::new(8, 5, 4)).beats(&boss));
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        let stats = Stats::parse("Hit Points: 104\nDamage: 8\nArmor: 1\n").unwrap();
+        assert_eq!((stats.hp, stats.damage, stats.armor), (104, 8, 1));
+
+        assert!(matches!(
+            Stats::parse("Hit Points: 104\nDamage: 8"),
+            Err(Error::MissingStat("Armor"))
+        ));
+        assert!(matches!(
+            Stats::parse("Hit Points: 300\nDamage: 8\nArmor: 1"),
+            Err(Error::BadStat(_))
+        ));
+        assert!(matches!(
+            Stats::parse("Hit Points: 1\nDamage: 8\nArmor: 1\nArmor: 2"),
+            Err(Error::BadStat(_))
+        ));
+    }
+
+    #[test]
+    fn test_shop_validation() {
+        let item = |name: &str, damage: u16| {
+            format!(r#"{{ "name": "{name}", "cost": 1, "damage": {damage}, "armor": 0 }}"#)
+        };
+        let shop = |weapons: &[String], rings: &[String]| {
+            format!(
+                r#"{{ "weapons": [{}], "armor": [], "rings": [{}] }}"#,
+                weapons.join(","),
+                rings.join(",")
+            )
+        };
+
+        assert!(Shop::load(&shop(&[item("Dagger", 4)], &[item("Ring", 1)])).is_ok());
+        assert!(matches!(
+            Shop::load(&shop(&[], &[item("Ring", 1)])),
+            Err(Error::NoWeapon)
+        ));
+        assert!(matches!(
+            Shop::load(&shop(&[item("Dagger", 4)], &[item("Dagger", 1)])),
+            Err(Error::DuplicateItem(_))
+        ));
+        assert!(matches!(
+            Shop::load(&shop(&[item("Axe", 200)], &[item("A", 50), item("B", 10)])),
+            Err(Error::StatsOverflow)
+        ));
+        assert!(matches!(
+            Shop::load(&shop(&[item("Axe", 256)], &[])),
+            Err(Error::Json(_))
+        ));
+    }
 }
@@ -1,50 +1,48 @@
-const SPELLS: &[Spell] = &[
-    Spell {
-        name: "Magic Missile",
-        cost: 53,
-        damage: 4,
-        self_heal: 0,
-        effect: None,
-        target: Target::Other,
-    },
-    Spell {
-        name: "Drain",
-        cost: 73,
-        damage: 2,
-        self_heal: 2,
-        effect: None,
-        target: Target::Other,
-    },
-    Spell {
-        name: "Shield",
-        cost: 113,
-        damage: 0,
-        self_heal: 0,
-        effect: Some(SHIELD),
-        target: Target::Itself,
-    },
-    Spell {
-        name: "Poison",
-        cost: 173,
-        damage: 0,
-        self_heal: 0,
-        effect: Some(DMG_OVER_TIME),
-        target: Target::Other,
-    },
-    Spell {
-        name: "Mana Regen",
-        cost: 229,
-        damage: 0,
-        self_heal: 0,
-        effect: Some(MANA_REGEN),
-        target: Target::Itself,
-    },
-];
-
-const SHIELD: Effect = Effect::Shield(7, 6);
-const MANA_REGEN: Effect = Effect::ManaRegen(101, 5);
-const DMG_OVER_TIME: Effect = Effect::DmgOverTime(3, 6);
-const MAX_EFFECTS: usize = 3;
+mod search;
+
+use serde::Deserialize;
+
+use search::{dfs, dijkstra, solve};
+
+/* ---------- */
+
+const NORMAL: Rules = Rules {
+    player_hp: 50,
+    player_mana: 500,
+    boss_hp: 71,
+    boss_damage: 10,
+    hp_drain: 0,
+};
+
+/* ---------- */
+
+/// Parameters of a game: the starting stats of both sides and how many hp the player loses at
+/// the start of each of its turns.
+#[derive(Debug, Clone, Copy)]
+struct Rules {
+    player_hp: i16,
+    player_mana: i16,
+    boss_hp: i16,
+    boss_damage: i16,
+    hp_drain: i16,
+}
+
+impl Rules {
+    const fn hard(self) -> Self {
+        Self {
+            hp_drain: 1,
+            ..self
+        }
+    }
+
+    fn player<'a>(&self) -> Entity<'a> {
+        Entity::new("Player", self.player_hp, 0, self.player_mana, 0)
+    }
+
+    fn boss<'a>(&self) -> Entity<'a> {
+        Entity::new("Boss", self.boss_hp, self.boss_damage, 0, 0)
+    }
+}
 
 /* ---------- */
 
@@ -52,70 +50,99 @@ const MAX_EFFECTS: usize = 3;
 enum Err {
     OutOfMana,
     EffectAlreadyApplied,
+    InvalidSpellbook(String),
 }
 
 impl std::fmt::Display for Err {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let msg = match self {
-            Self::OutOfMana => "out of mana",
-            Self::EffectAlreadyApplied => "effect already applied to target",
-        };
-
-        write!(f, "{}", msg)
+        match self {
+            Self::OutOfMana => write!(f, "out of mana"),
+            Self::EffectAlreadyApplied => write!(f, "effect already applied to target"),
+            Self::InvalidSpellbook(msg) => write!(f, "invalid spellbook : {msg}"),
+        }
     }
 }
 
 /* ---------- */
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
 enum Target {
     Itself,
+    #[default]
     Other,
 }
 
 /* ---------- */
 
-#[derive(Debug, Copy, Clone)]
-enum Effect {
-    Shield(i16, u8),
-    ManaRegen(i16, u8),
-    DmgOverTime(i16, u8),
+/// What an effect does to the entity it is applied to, on each tick while it lasts. Armor is
+/// only granted while the effect is active.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Effect {
+    damage: i16,
+    heal: i16,
+    mana: i16,
+    armor: i16,
+    duration: u8,
 }
 
-impl Effect {
-    fn ticks(&self) -> u8 {
-        match self {
-            Self::Shield(_, t) | Self::ManaRegen(_, t) | Self::DmgOverTime(_, t) => *t,
-        }
-    }
+/// An effect applied to an entity, along with the name of the spell it comes from and its
+/// remaining ticks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct ActiveEffect<'a> {
+    name: &'a str,
+    effect: Effect,
+    ticks: u8,
+}
 
-    fn decrement_ticks(&mut self) {
-        match self {
-            Self::Shield(_, t) | Self::ManaRegen(_, t) | Self::DmgOverTime(_, t) => *t -= 1,
+impl ActiveEffect<'_> {
+    /// What happens when the effect ticks, as the puzzle statement words it.
+    fn describe_tick(&self) -> String {
+        let ticks = self.ticks - 1;
+        let name = self.name;
+        let actions = [
+            (self.effect.damage, "deals {} damage"),
+            (self.effect.heal, "heals {} hit points"),
+            (self.effect.mana, "provides {} mana"),
+        ]
+        .into_iter()
+        .filter(|&(amount, _)| amount != 0)
+        .map(|(amount, action)| action.replace("{}", &amount.to_string()))
+        .collect::<Vec<_>>();
+
+        let mut desc = match actions.is_empty() {
+            true => format!("{name}'s timer is now {ticks}."),
+            false => format!(
+                "{name} {}; its timer is now {ticks}.",
+                actions.join(" and ")
+            ),
+        };
+
+        if ticks == 0 {
+            desc += &match self.effect.armor {
+                0 => format!(" {name} wears off."),
+                armor => format!(" {name} wears off, decreasing armor by {armor}."),
+            };
         }
-    }
-}
 
-impl PartialEq for Effect {
-    fn eq(&self, other: &Self) -> bool {
-        matches!(
-            (self, other),
-            (Self::Shield(_, _), Self::Shield(_, _))
-                | (Self::ManaRegen(_, _), Self::ManaRegen(_, _))
-                | (Self::DmgOverTime(_, _), Self::DmgOverTime(_, _))
-        )
+        desc
     }
 }
 
 /* ---------- */
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct Spell {
-    name: &'static str,
+    name: String,
     pub cost: i16,
+    #[serde(default)]
     pub damage: i16,
-    pub self_heal: i16,
+    #[serde(default)]
+    pub heal: i16,
+    #[serde(default)]
     pub effect: Option<Effect>,
+    #[serde(default)]
     pub target: Target,
 }
 
@@ -125,19 +152,52 @@ impl std::fmt::Display for Spell {
     }
 }
 
+/// Loads the spells the player can cast. Effects have to last at least one tick, and no amount
+/// can be negative: spells costing mana back would break the cheapest-first search.
+fn load_spellbook(json: &str) -> Result<Vec<Spell>, Err> {
+    let spells: Vec<Spell> =
+        serde_json::from_str(json).map_err(|err| Err::InvalidSpellbook(err.to_string()))?;
+
+    for spell in &spells {
+        if spell.effect.is_some_and(|effect| effect.duration == 0) {
+            return Err(Err::InvalidSpellbook(format!("{spell} never ticks")));
+        }
+
+        let effect = spell.effect.unwrap_or_default();
+        let amounts = [
+            ("cost", spell.cost),
+            ("damage", spell.damage),
+            ("heal", spell.heal),
+            ("effect damage", effect.damage),
+            ("effect heal", effect.heal),
+            ("effect mana", effect.mana),
+            ("effect armor", effect.armor),
+        ];
+
+        if let Some((field, _)) = amounts.iter().find(|(_, amount)| *amount < 0) {
+            return Err(Err::InvalidSpellbook(format!(
+                "{spell} has a negative {field}"
+            )));
+        }
+    }
+
+    Ok(spells)
+}
+
 /* ---------- */
 
-#[derive(Debug, Copy, Clone)]
-struct Entity {
+/// A side of the battle. Its active effects borrow their names from the spellbook.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Entity<'a> {
     name: &'static str,
     hp: i16,
     mana: i16,
     damage: i16,
     armor: i16,
-    effects: [Option<Effect>; MAX_EFFECTS],
+    effects: Vec<ActiveEffect<'a>>,
 }
 
-impl Entity {
+impl<'a> Entity<'a> {
     fn new(name: &'static str, hp: i16, damage: i16, mana: i16, armor: i16) -> Self {
         Self {
             name,
@@ -145,16 +205,23 @@ impl Entity {
             mana,
             damage,
             armor,
-            effects: [None; MAX_EFFECTS],
+            effects: vec![],
         }
     }
 
+    #[inline(always)]
+    fn armor(&self) -> i16 {
+        self.armor + self.effects.iter().map(|eff| eff.effect.armor).sum::<i16>()
+    }
+
     #[inline(always)]
     fn attacks(&self, target: &mut Self) {
-        target.hp -= if target.armor >= self.damage {
+        let armor = target.armor();
+
+        target.hp -= if armor >= self.damage {
             1
         } else {
-            self.damage - target.armor
+            self.damage - armor
         };
     }
 
@@ -164,44 +231,30 @@ impl Entity {
             return Err(Err::OutOfMana);
         }
 
-        if let Some(effect) = &spell.effect {
-            return match spell.target {
-                Target::Itself => {
-                    if self.is_effect_applied(effect) {
-                        Err(Err::EffectAlreadyApplied)
-                    } else {
-                        Ok(())
-                    }
-                }
-                Target::Other => {
-                    if target.is_effect_applied(effect) {
-                        Err(Err::EffectAlreadyApplied)
-                    } else {
-                        Ok(())
-                    }
-                }
-            };
+        let holder = match spell.target {
+            Target::Itself => self,
+            Target::Other => target,
+        };
+
+        if spell.effect.is_some() && holder.is_effect_applied(&spell.name) {
+            return Err(Err::EffectAlreadyApplied);
         }
 
         Ok(())
     }
 
-    fn casts(&mut self, spell: &Spell, target: &mut Self) {
+    fn casts(&mut self, spell: &'a Spell, target: &mut Self) {
         self.mana -= spell.cost;
+        target.hp -= spell.damage;
+        self.hp += spell.heal;
 
-        match spell.target {
-            Target::Itself => {
-                if let Some(effect) = spell.effect {
-                    self.apply_effect(effect);
-                }
-            }
-            Target::Other => {
-                if let Some(effect) = spell.effect {
-                    target.apply_effect(effect);
-                }
-                target.hp -= spell.damage;
-                self.hp += spell.self_heal;
-            }
+        if let Some(effect) = spell.effect {
+            let holder = match spell.target {
+                Target::Itself => self,
+                Target::Other => target,
+            };
+
+            holder.apply_effect(&spell.name, effect);
         }
     }
 
@@ -211,66 +264,52 @@ impl Entity {
     }
 
     #[inline(always)]
-    fn is_effect_applied(&self, effect: &Effect) -> bool {
-        self.effects
-            .iter()
-            .filter(|eff| eff.is_some())
-            .any(|eff| eff.expect("a valid effect") == *effect)
+    fn is_effect_applied(&self, name: &str) -> bool {
+        self.effects.iter().any(|eff| eff.name == name)
     }
 
-    fn apply_effect(&mut self, effect: Effect) {
-        let eff = self
-            .effects
-            .iter_mut()
-            .find(|eff| eff.is_none())
-            .expect("expected at least one free effect spot");
-        let effect = eff.insert(effect);
-
-        if let Effect::Shield(armor, _) = effect {
-            self.armor += *armor;
-        }
+    /// Effects are kept sorted by name so that entities under the same effects compare equal
+    /// whatever order they were cast in.
+    fn apply_effect(&mut self, name: &'a str, effect: Effect) {
+        let index = self.effects.partition_point(|eff| eff.name < name);
+
+        self.effects.insert(
+            index,
+            ActiveEffect {
+                name,
+                effect,
+                ticks: effect.duration,
+            },
+        );
     }
 
     fn update_effects(&mut self) {
         self.effects.iter_mut().for_each(|eff| {
-            let mut ended = false;
-
-            if let Some(eff) = eff {
-                match eff {
-                    Effect::ManaRegen(mana_regen, _) => self.mana += *mana_regen,
-                    Effect::DmgOverTime(dmg, _) => self.hp -= *dmg,
-                    _ => (),
-                }
-
-                eff.decrement_ticks();
-
-                if eff.ticks() == 0 {
-                    ended = true
-                }
-            }
-
-            if ended {
-                if let Some(Effect::Shield(armor, _)) = eff.take() {
-                    self.armor -= armor;
-                }
-            }
+            self.hp += eff.effect.heal - eff.effect.damage;
+            self.mana += eff.effect.mana;
+            eff.ticks -= 1;
         });
+
+        self.effects.retain(|eff| eff.ticks > 0);
     }
 }
 
-impl std::fmt::Display for Entity {
+impl std::fmt::Display for Entity<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
             "- {} has {} hp, {} mana, {} armor",
-            self.name, self.hp, self.mana, self.armor
+            self.name,
+            self.hp,
+            self.mana,
+            self.armor()
         )
     }
 }
 
 /* ---------- */
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Turn {
     Player,
     Boss,
@@ -296,59 +335,323 @@ impl std::fmt::Display for Turn {
 
 /* ---------- */
 
-fn sim(
-    turn: Turn,
-    mut player: Entity,
-    mut boss: Entity,
-    mana_spent: i16,
-    min_mana_spent: &mut i16,
-) {
-    player.update_effects();
-    boss.update_effects();
-
-    if !boss.is_alive() {
-        if mana_spent < *min_mana_spent {
-            *min_mana_spent = mana_spent;
+fn log_effects(entity: &Entity<'_>, log: &mut Vec<String>) {
+    entity
+        .effects
+        .iter()
+        .for_each(|effect| log.push(effect.describe_tick()));
+}
+
+/// Plays `spells` in order under `rules` and returns the battle log, in the format of the
+/// puzzle statement. Stops as soon as a side dies, a spell can't be cast or the spells run out.
+fn replay(rules: &Rules, spells: &[&Spell]) -> Vec<String> {
+    let mut log = vec![];
+    let (mut player, mut boss) = (rules.player(), rules.boss());
+    let mut spells = spells.iter();
+    let mut turn = Turn::Player;
+
+    loop {
+        log.push(format!("-- {turn} turn --"));
+
+        if let Turn::Player = turn {
+            if rules.hp_drain > 0 {
+                player.hp -= rules.hp_drain;
+                log.push(format!("Player loses {} hit points.", rules.hp_drain));
+            }
+
+            if !player.is_alive() {
+                log.push("This kills the player, and the boss wins.".to_string());
+                break;
+            }
         }
-        return;
+
+        log.push(player.to_string());
+        log.push(boss.to_string());
+
+        log_effects(&player, &mut log);
+        log_effects(&boss, &mut log);
+        player.update_effects();
+        boss.update_effects();
+
+        if !boss.is_alive() {
+            log.push("This kills the boss, and the player wins.".to_string());
+            break;
+        }
+
+        match turn {
+            Turn::Boss => {
+                let hp = player.hp;
+
+                boss.attacks(&mut player);
+                log.push(format!("Boss attacks for {} damage.", hp - player.hp));
+
+                if !player.is_alive() {
+                    log.push("This kills the player, and the boss wins.".to_string());
+                    break;
+                }
+            }
+            Turn::Player => {
+                let Some(spell) = spells.next() else {
+                    log.push("Player has no spell left to cast.".to_string());
+                    break;
+                };
+
+                if let Err(err) = player.can_cast(spell, &boss) {
+                    log.push(format!("Player can't cast {spell}: {err}."));
+                    break;
+                }
+
+                player.casts(spell, &mut boss);
+                log.push(format!("Player casts {spell}."));
+
+                if !boss.is_alive() {
+                    log.push("This kills the boss, and the player wins.".to_string());
+                    break;
+                }
+            }
+        }
+
+        log.push(String::new());
+        turn = turn.next();
     }
 
-    if !player.is_alive() {
-        return;
+    log
+}
+
+/* ---------- */
+
+fn part1((rules, book): &(Rules, Vec<Spell>)) -> i16 {
+    solve(rules, book).expect("a winnable game").mana_spent
+}
+
+/* ---------- */
+
+fn part2((rules, book): &(Rules, Vec<Spell>)) -> i16 {
+    solve(&rules.hard(), book)
+        .expect("a winnable game")
+        .mana_spent
+}
+
+/* ---------- */
+
+fn bench(book: &[Spell]) {
+    for rules in [NORMAL, NORMAL.hard()] {
+        let (depth_first, best_first) = (dfs(&rules, book), dijkstra(&rules, book));
+
+        println!(
+            "[BENCH] drain {}: dfs expands {} states, dijkstra {}",
+            rules.hp_drain, depth_first.expanded, best_first.expanded
+        );
+
+        println!("[BENCH] dfs");
+        utils::bench!(std::hint::black_box(dfs(&rules, book)));
+
+        println!("[BENCH] dijkstra");
+        utils::bench!(std::hint::black_box(dijkstra(&rules, book)));
     }
+}
+
+/* ---------- */
+
+enum Mode {
+    Answers,
+    Bench,
+    Replay,
+}
+
+/* ---------- */
 
-    match turn {
-        Turn::Boss => {
-            boss.attacks(&mut player);
-            sim(turn.next(), player, boss, mana_spent, min_mana_spent);
+fn main() {
+    let mut mode = Mode::Answers;
+    let mut path = None;
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "bench" => mode = Mode::Bench,
+            "replay" => mode = Mode::Replay,
+            _ => path = Some(arg),
         }
-        Turn::Player => SPELLS.iter().for_each(|spell| {
-            if mana_spent + spell.cost < *min_mana_spent && player.can_cast(spell, &boss).is_ok() {
-                let mut tmp_player = player;
-                let mut tmp_boss = boss;
-
-                tmp_player.casts(spell, &mut tmp_boss);
-                sim(
-                    turn.next(),
-                    tmp_player,
-                    tmp_boss,
-                    mana_spent + spell.cost,
-                    min_mana_spent,
-                );
+    }
+
+    let book = match path {
+        Some(path) => load_spellbook(&std::fs::read_to_string(path).expect("a readable file")),
+        None => load_spellbook(utils::input_str!("spells.json")),
+    }
+    .expect("a valid spellbook");
+
+    match mode {
+        Mode::Bench => bench(&book),
+        Mode::Replay => {
+            for rules in [NORMAL, NORMAL.hard()] {
+                let victory = solve(&rules, &book).expect("a winnable game");
+
+                replay(&rules, &victory.spells)
+                    .iter()
+                    .for_each(|line| println!("{line}"));
+                println!();
             }
-        }),
+        }
+        Mode::Answers => {
+            let input = (NORMAL, book);
+
+            utils::answer!(&input);
+        }
     }
 }
 
 /* ---------- */
 
-fn main() {
-    let boss = Entity::new("Boss", 71, 10, 0, 0);
-    let player = Entity::new("Player", 50, 0, 500, 0);
-    let turn = Turn::Player;
-    let mut min_mana_spent = i16::MAX;
+#[cfg(test)]
+mod test {
+    use crate::search::{dfs, dijkstra};
+    use crate::{load_spellbook, replay, solve, Err, Rules, Spell, NORMAL};
+
+    const EXAMPLE: Rules = Rules {
+        player_hp: 10,
+        player_mana: 250,
+        boss_hp: 13,
+        boss_damage: 8,
+        hp_drain: 0,
+    };
+
+    fn book() -> Vec<Spell> {
+        load_spellbook(utils::input_str!("spells.json")).unwrap()
+    }
 
-    sim(turn, player, boss, 0, &mut min_mana_spent);
+    fn names(rules: &Rules) -> Vec<String> {
+        solve(rules, &book())
+            .expect("a winnable game")
+            .spells
+            .iter()
+            .map(|spell| spell.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_examples() {
+        assert_eq!(names(&EXAMPLE), ["Poison", "Magic Missile"]);
+        assert_eq!(
+            names(&Rules {
+                boss_hp: 14,
+                ..EXAMPLE
+            }),
+            ["Mana Regen", "Shield", "Drain", "Poison", "Magic Missile"]
+        );
+    }
+
+    #[test]
+    fn test_hard_mode() {
+        assert!(solve(&EXAMPLE.hard(), &book()).is_none());
+    }
+
+    #[test]
+    fn test_replay() {
+        let book = book();
+        let victory = solve(&EXAMPLE, &book).unwrap();
+        let log = replay(&EXAMPLE, &victory.spells);
+
+        assert_eq!(
+            log,
+            [
+                "-- Player turn --",
+                "- Player has 10 hp, 250 mana, 0 armor",
+                "- Boss has 13 hp, 0 mana, 0 armor",
+                "Player casts Poison.",
+                "",
+                "-- Boss turn --",
+                "- Player has 10 hp, 77 mana, 0 armor",
+                "- Boss has 13 hp, 0 mana, 0 armor",
+                "Poison deals 3 damage; its timer is now 5.",
+                "Boss attacks for 8 damage.",
+                "",
+                "-- Player turn --",
+                "- Player has 2 hp, 77 mana, 0 armor",
+                "- Boss has 10 hp, 0 mana, 0 armor",
+                "Poison deals 3 damage; its timer is now 4.",
+                "Player casts Magic Missile.",
+                "",
+                "-- Boss turn --",
+                "- Player has 2 hp, 24 mana, 0 armor",
+                "- Boss has 3 hp, 0 mana, 0 armor",
+                "Poison deals 3 damage; its timer is now 3.",
+                "This kills the boss, and the player wins.",
+            ]
+        );
+
+        let stuck = replay(&EXAMPLE, &victory.spells[..1]);
+        assert_eq!(stuck.last().unwrap(), "Player has no spell left to cast.");
+
+        let hard = replay(&EXAMPLE.hard(), &victory.spells);
+        assert_eq!(
+            hard.last().unwrap(),
+            "This kills the player, and the boss wins."
+        );
+    }
+
+    #[test]
+    fn test_custom_spellbook() {
+        let book = load_spellbook(
+            r#"[
+                { "name": "Jab", "cost": 10, "damage": 1 },
+                {
+                    "name": "Regen",
+                    "cost": 20,
+                    "target": "Itself",
+                    "effect": { "duration": 4, "heal": 3, "armor": 1 }
+                }
+            ]"#,
+        )
+        .unwrap();
+        let rules = Rules {
+            player_hp: 8,
+            player_mana: 100,
+            boss_hp: 3,
+            boss_damage: 4,
+            hp_drain: 0,
+        };
+        let victory = solve(&rules, &book).unwrap();
+        let log = replay(&rules, &victory.spells);
+
+        assert_eq!(victory.mana_spent, 50);
+        assert_eq!(victory.spells[0].to_string(), "Jab");
+        assert!(log.contains(&"Regen heals 3 hit points; its timer is now 3.".to_string()));
+
+        // Spellbooks built at runtime, with escaped names, load as well.
+        let json = String::from(r#"[{ "name": "Jab \"2\"", "cost": 5, "damage": 9 }]"#);
+        let book = load_spellbook(&json).unwrap();
+        assert_eq!(book[0].name, r#"Jab "2""#);
+        assert_eq!(solve(&rules, &book).unwrap().mana_spent, 5);
+
+        assert!(matches!(
+            load_spellbook(r#"[{ "name": "Nap", "cost": 1, "effect": { "duration": 0 } }]"#),
+            Err(Err::InvalidSpellbook(_))
+        ));
+        assert!(matches!(
+            load_spellbook(r#"[{ "name": "Nap", "cost": 1, "speed": 2 }]"#),
+            Err(Err::InvalidSpellbook(_))
+        ));
+        for nap in [
+            r#"[{ "name": "Nap", "cost": -1 }]"#,
+            r#"[{ "name": "Nap", "cost": 1, "heal": -2 }]"#,
+            r#"[{ "name": "Nap", "cost": 1, "effect": { "duration": 2, "mana": -5 } }]"#,
+            r#"[{ "name": "Nap", "cost": 1, "effect": { "duration": -2 } }]"#,
+        ] {
+            assert!(matches!(load_spellbook(nap), Err(Err::InvalidSpellbook(_))));
+        }
+    }
 
-    println!("result = {}", min_mana_spent);
+    #[test]
+    fn test_searches_agree() {
+        let book = book();
+
+        for rules in [EXAMPLE, NORMAL, NORMAL.hard()] {
+            let (depth_first, best_first) = (dfs(&rules, &book), dijkstra(&rules, &book));
+
+            assert_eq!(
+                depth_first.victory.map(|victory| victory.mana_spent),
+                best_first.victory.map(|victory| victory.mana_spent)
+            );
+            assert!(best_first.expanded < depth_first.expanded);
+        }
+    }
 }
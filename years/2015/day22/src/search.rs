@@ -0,0 +1,263 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::{Entity, Rules, Spell, Turn};
+
+/* ---------- */
+
+/// Cheapest win found and the spells cast to get there.
+#[derive(Debug, Clone)]
+pub struct Victory<'a> {
+    pub mana_spent: i16,
+    pub spells: Vec<&'a Spell>,
+}
+
+/// Result of a search, along with the number of game states it had to expand.
+#[derive(Debug)]
+pub struct Outcome<'a> {
+    pub victory: Option<Victory<'a>>,
+    pub expanded: usize,
+}
+
+/* ---------- */
+
+enum Status {
+    Won,
+    Lost,
+    Ongoing,
+}
+
+/// Everything that happens at the start of a turn before anyone acts: the hp drain on player
+/// turns, then every effect ticks.
+fn start_turn(rules: &Rules, turn: Turn, player: &mut Entity<'_>, boss: &mut Entity<'_>) -> Status {
+    if let Turn::Player = turn {
+        player.hp -= rules.hp_drain;
+
+        if !player.is_alive() {
+            return Status::Lost;
+        }
+    }
+
+    player.update_effects();
+    boss.update_effects();
+
+    if !boss.is_alive() {
+        Status::Won
+    } else if !player.is_alive() {
+        Status::Lost
+    } else {
+        Status::Ongoing
+    }
+}
+
+/* ---------- */
+
+/// Depth first search over the spells to cast, pruning every branch already costlier than the
+/// cheapest win found so far.
+struct Search<'a> {
+    rules: &'a Rules,
+    book: &'a [Spell],
+    path: Vec<&'a Spell>,
+    best: Option<Victory<'a>>,
+    expanded: usize,
+}
+
+impl<'a> Search<'a> {
+    fn sim(&mut self, turn: Turn, mut player: Entity<'a>, mut boss: Entity<'a>, mana_spent: i16) {
+        self.expanded += 1;
+
+        match start_turn(self.rules, turn, &mut player, &mut boss) {
+            Status::Won => {
+                if self
+                    .best
+                    .as_ref()
+                    .is_none_or(|best| mana_spent < best.mana_spent)
+                {
+                    self.best = Some(Victory {
+                        mana_spent,
+                        spells: self.path.clone(),
+                    });
+                }
+                return;
+            }
+            Status::Lost => return,
+            Status::Ongoing => (),
+        }
+
+        match turn {
+            Turn::Boss => {
+                boss.attacks(&mut player);
+                self.sim(turn.next(), player, boss, mana_spent);
+            }
+            Turn::Player => {
+                let book = self.book;
+
+                book.iter().for_each(|spell| {
+                    let bound = self.best.as_ref().map_or(i16::MAX, |best| best.mana_spent);
+
+                    let Some(cost) = mana_spent.checked_add(spell.cost) else {
+                        return;
+                    };
+
+                    if cost < bound && player.can_cast(spell, &boss).is_ok() {
+                        let mut tmp_player = player.clone();
+                        let mut tmp_boss = boss.clone();
+
+                        tmp_player.casts(spell, &mut tmp_boss);
+                        self.path.push(spell);
+                        self.sim(turn.next(), tmp_player, tmp_boss, cost);
+                        self.path.pop();
+                    }
+                })
+            }
+        }
+    }
+}
+
+pub fn dfs<'a>(rules: &'a Rules, book: &'a [Spell]) -> Outcome<'a> {
+    let mut search = Search {
+        rules,
+        book,
+        path: vec![],
+        best: None,
+        expanded: 0,
+    };
+
+    search.sim(Turn::Player, rules.player(), rules.boss(), 0);
+
+    Outcome {
+        victory: search.best,
+        expanded: search.expanded,
+    }
+}
+
+/* ---------- */
+
+/// A game at the start of a turn. Effects are kept sorted on the entities, so two games reached
+/// through different spell orders hash the same.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct State<'a> {
+    turn: Turn,
+    player: Entity<'a>,
+    boss: Entity<'a>,
+}
+
+struct Node<'a> {
+    state: State<'a>,
+    parent: Option<usize>,
+    spell: Option<&'a Spell>,
+}
+
+fn path<'a>(nodes: &[Node<'a>], mut index: usize) -> Vec<&'a Spell> {
+    let mut spells = vec![];
+
+    loop {
+        let node = &nodes[index];
+
+        spells.extend(node.spell);
+        match node.parent {
+            Some(parent) => index = parent,
+            None => break,
+        }
+    }
+
+    spells.reverse();
+    spells
+}
+
+/// Dijkstra over the game states, the mana spent being the distance. Every state is expanded at
+/// most once, with the cheapest mana spent to reach it.
+pub fn dijkstra<'a>(rules: &Rules, book: &'a [Spell]) -> Outcome<'a> {
+    let start = State {
+        turn: Turn::Player,
+        player: rules.player(),
+        boss: rules.boss(),
+    };
+    let mut costs = HashMap::from([(start.clone(), 0)]);
+    let mut nodes = vec![Node {
+        state: start,
+        parent: None,
+        spell: None,
+    }];
+    let mut queue = BinaryHeap::from([Reverse((0, 0))]);
+    let mut expanded = 0;
+
+    while let Some(Reverse((mana_spent, index))) = queue.pop() {
+        let State {
+            turn,
+            mut player,
+            mut boss,
+        } = nodes[index].state.clone();
+
+        if costs[&nodes[index].state] < mana_spent {
+            continue;
+        }
+
+        expanded += 1;
+
+        match start_turn(rules, turn, &mut player, &mut boss) {
+            Status::Won => {
+                return Outcome {
+                    victory: Some(Victory {
+                        mana_spent,
+                        spells: path(&nodes, index),
+                    }),
+                    expanded,
+                }
+            }
+            Status::Lost => continue,
+            Status::Ongoing => (),
+        }
+
+        let moves = match turn {
+            Turn::Boss => {
+                boss.attacks(&mut player);
+                vec![(None, player, boss)]
+            }
+            Turn::Player => book
+                .iter()
+                .filter(|spell| player.can_cast(spell, &boss).is_ok())
+                .map(|spell| {
+                    let mut tmp_player = player.clone();
+                    let mut tmp_boss = boss.clone();
+
+                    tmp_player.casts(spell, &mut tmp_boss);
+                    (Some(spell), tmp_player, tmp_boss)
+                })
+                .collect(),
+        };
+
+        for (spell, player, boss) in moves {
+            let Some(cost) = mana_spent.checked_add(spell.map_or(0, |spell| spell.cost)) else {
+                continue;
+            };
+            let state = State {
+                turn: turn.next(),
+                player,
+                boss,
+            };
+
+            if costs.get(&state).is_some_and(|&old| old <= cost) {
+                continue;
+            }
+
+            costs.insert(state.clone(), cost);
+            nodes.push(Node {
+                state,
+                parent: Some(index),
+                spell,
+            });
+            queue.push(Reverse((cost, nodes.len() - 1)));
+        }
+    }
+
+    Outcome {
+        victory: None,
+        expanded,
+    }
+}
+
+#[inline]
+pub fn solve<'a>(rules: &Rules, book: &'a [Spell]) -> Option<Victory<'a>> {
+    dijkstra(rules, book).victory
+}
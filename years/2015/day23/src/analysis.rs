@@ -0,0 +1,93 @@
+use utils::vm::{Cfg, Loop, Program, Reg};
+
+use crate::Instruction;
+
+/* ---------- */
+
+/// A loop counting, in `counter`, the steps the Collatz sequence starting at `value` takes to
+/// reach 1:
+///
+/// ```text
+/// L0:  jio value, end
+///      inc counter
+///      jie value, L1
+///      tpl value
+///      inc value
+///      jmp L0
+/// L1:  hlf value
+///      jmp L0
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Collatz {
+    pub value: Reg,
+    pub counter: Reg,
+}
+
+impl Collatz {
+    /// Checks whether the blocks of `lp` are made of the instructions of a Collatz loop, in
+    /// whatever order.
+    pub fn recognize(prog: &Program<Instruction>, cfg: &Cfg, lp: &Loop) -> Option<Self> {
+        let instrs = lp
+            .blocks
+            .iter()
+            .flat_map(|&id| {
+                let block = &cfg.blocks()[id];
+                (block.start..block.end).map(|pc| prog[pc])
+            })
+            .collect::<Vec<_>>();
+
+        let value = instrs.iter().find_map(|instr| match instr {
+            Instruction::JumpIfOne(reg, _) => Some(*reg),
+            _ => None,
+        })?;
+
+        let counter = instrs.iter().find_map(|instr| match instr {
+            Instruction::Increment(reg) if *reg != value => Some(*reg),
+            _ => None,
+        })?;
+
+        let expected = [
+            Instruction::Increment(value),
+            Instruction::Triple(value),
+            Instruction::Half(value),
+        ];
+
+        let has_even_test = instrs
+            .iter()
+            .any(|instr| matches!(instr, Instruction::JumpIfEven(reg, _) if *reg == value));
+
+        let only_known = instrs.iter().all(|instr| match instr {
+            Instruction::Increment(reg) => *reg == value || *reg == counter,
+            Instruction::Half(reg) | Instruction::Triple(reg) => *reg == value,
+            Instruction::JumpIfEven(reg, _) | Instruction::JumpIfOne(reg, _) => *reg == value,
+            Instruction::Jump(_) => true,
+        });
+
+        (has_even_test && only_known && expected.iter().all(|instr| instrs.contains(instr)))
+            .then_some(Self { value, counter })
+    }
+}
+
+/* ---------- */
+
+/// Describes every loop of the program, one per line.
+pub fn describe_loops(prog: &Program<Instruction>) -> String {
+    let cfg = Cfg::new(prog);
+
+    cfg.loops()
+        .iter()
+        .map(|lp| {
+            let header = &cfg.blocks()[lp.header];
+            let range = format!("loop at {}, {} block(s)", header.start, lp.blocks.len());
+
+            match Collatz::recognize(prog, &cfg, lp) {
+                Some(Collatz { value, counter }) => format!(
+                    "{range}: counts in {} the Collatz steps taking {} down to 1\n",
+                    counter.name(),
+                    value.name()
+                ),
+                None => format!("{range}\n"),
+            }
+        })
+        .collect()
+}
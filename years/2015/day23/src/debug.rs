@@ -0,0 +1,196 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+
+use utils::vm::{Breakpoint, Cmp, Debugger, Machine, Program, Reg, State, Stop, Word};
+
+use crate::{Instruction, REGISTER_COUNT, STEP_LIMIT};
+
+/* ---------- */
+
+const HELP: &str = "\
+commands:
+  step [n]                  execute n instructions, 1 by default
+  continue                  run until a breakpoint is hit, the program ends or fails
+  break <idx>               stop before the instruction at index <idx>
+  break <reg> <cmp> <val>   stop once the register compares to <val> (==, !=, <, <=, >, >=)
+  delete <n>                remove the n-th breakpoint
+  breakpoints               list the breakpoints
+  watch <reg>               print the register after every command
+  unwatch <reg>             stop watching the register
+  set <reg> <val>           change the value of a register
+  regs                      print every register
+  list                      print the program
+  record on|off             start or stop recording the execution trace
+  dump <file>               write the recorded trace to a file
+  hot [n]                   print the n most executed instructions, 5 by default
+  help                      print this message
+  quit                      leave the debugger";
+
+/* ---------- */
+
+fn parse_reg(s: &str) -> Result<Reg, String> {
+    let reg = Reg::parse(s)?;
+
+    match reg.0 < REGISTER_COUNT {
+        true => Ok(reg),
+        false => Err(format!("no such register '{s}'")),
+    }
+}
+
+/* ---------- */
+
+fn parse_num<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse::<T>().map_err(|_| format!("invalid number '{s}'"))
+}
+
+/* ---------- */
+
+fn print_location(dbg: &Debugger<Instruction>, out: &mut impl Write) -> io::Result<()> {
+    let machine = dbg.machine();
+
+    match (machine.state(), dbg.program().get(machine.pc())) {
+        (State::Running, Some(instr)) => writeln!(out, "{:>4}  {instr}", machine.pc())?,
+        _ => writeln!(out, "halted after {} steps", machine.steps())?,
+    }
+
+    for (reg, val) in dbg.watched() {
+        writeln!(out, "      {} = {val}", reg.name())?;
+    }
+
+    Ok(())
+}
+
+/* ---------- */
+
+fn execute(
+    parts: &[&str],
+    dbg: &mut Debugger<Instruction>,
+    out: &mut impl Write,
+) -> Result<(), String> {
+    let prog = dbg.program();
+    let io_err = |err: io::Error| err.to_string();
+
+    match parts {
+        ["step"] | ["s"] | ["step", _] | ["s", _] => {
+            let n = match parts.get(1) {
+                Some(n) => parse_num(n)?,
+                None => 1,
+            };
+
+            for _ in 0..n {
+                match dbg.step() {
+                    Ok(State::Running) => (),
+                    Ok(State::Halted) => break,
+                    Err(err) => {
+                        writeln!(out, "error: {err}").map_err(io_err)?;
+                        break;
+                    }
+                }
+            }
+
+            print_location(dbg, out).map_err(io_err)?;
+        }
+        ["continue"] | ["c"] => {
+            match dbg.resume() {
+                Stop::Breakpoint(idx) => {
+                    let bp = dbg.breakpoints()[idx];
+                    writeln!(out, "breakpoint {idx} hit ({bp})").map_err(io_err)?;
+                }
+                Stop::Error(err) => writeln!(out, "error: {err}").map_err(io_err)?,
+                Stop::Halted => (),
+            }
+
+            print_location(dbg, out).map_err(io_err)?;
+        }
+        ["break", idx] | ["b", idx] => dbg.add_breakpoint(Breakpoint::At(parse_num(idx)?)),
+        ["break", reg, cmp, val] | ["b", reg, cmp, val] => {
+            let bp = Breakpoint::When(parse_reg(reg)?, Cmp::parse(cmp)?, parse_num::<Word>(val)?);
+            dbg.add_breakpoint(bp)
+        }
+        ["delete", n] => {
+            dbg.remove_breakpoint(parse_num(n)?)
+                .ok_or_else(|| format!("no breakpoint {n}"))?;
+        }
+        ["breakpoints"] => {
+            for (idx, bp) in dbg.breakpoints().iter().enumerate() {
+                writeln!(out, "{idx}: {bp}").map_err(io_err)?;
+            }
+        }
+        ["watch", reg] => dbg.watch(parse_reg(reg)?),
+        ["unwatch", reg] => dbg.unwatch(parse_reg(reg)?),
+        ["set", reg, val] => {
+            let reg = parse_reg(reg)?;
+            dbg.machine_mut().set_reg(reg, parse_num(val)?)
+        }
+        ["regs"] => {
+            let machine = dbg.machine();
+
+            for (idx, val) in machine.registers().values().iter().enumerate() {
+                writeln!(out, "{} = {val}", Reg(idx).name()).map_err(io_err)?;
+            }
+
+            writeln!(out, "pc = {}, steps = {}", machine.pc(), machine.steps()).map_err(io_err)?;
+        }
+        ["list"] => {
+            for (idx, instr) in prog.iter().enumerate() {
+                let marker = if idx == dbg.machine().pc() { ">" } else { " " };
+                writeln!(out, "{marker} {idx:>4}  {instr}").map_err(io_err)?;
+            }
+        }
+        ["record", "on"] => dbg.record(true),
+        ["record", "off"] => dbg.record(false),
+        ["dump", path] => {
+            let file = File::create(path).map_err(io_err)?;
+            dbg.write_trace(&mut BufWriter::new(file)).map_err(io_err)?;
+            writeln!(out, "{} entries written", dbg.trace().len()).map_err(io_err)?;
+        }
+        ["hot"] | ["hot", _] => {
+            let n = match parts.get(1) {
+                Some(n) => parse_num(n)?,
+                None => 5,
+            };
+
+            for (idx, count) in dbg.hot_spots(n) {
+                writeln!(out, "{count:>10}  {idx:>4}  {}", prog[idx]).map_err(io_err)?;
+            }
+        }
+        ["help"] => writeln!(out, "{HELP}").map_err(io_err)?,
+        _ => return Err(format!("unknown command '{}', try 'help'", parts.join(" "))),
+    }
+
+    Ok(())
+}
+
+/* ---------- */
+
+/// Reads debugger commands from stdin, one per line, until `quit` or EOF.
+pub fn run(prog: &Program<Instruction>) -> io::Result<()> {
+    let machine = Machine::new(REGISTER_COUNT).with_step_limit(STEP_LIMIT);
+    let mut dbg = Debugger::new(prog, machine);
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    print_location(&dbg, &mut stdout)?;
+    write!(stdout, "> ")?;
+    stdout.flush()?;
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let parts = line.split_ascii_whitespace().collect::<Vec<_>>();
+
+        match parts.as_slice() {
+            [] => (),
+            ["quit"] | ["q"] => break,
+            parts => {
+                if let Err(err) = execute(parts, &mut dbg, &mut stdout) {
+                    writeln!(stdout, "{err}")?;
+                }
+            }
+        }
+
+        write!(stdout, "> ")?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
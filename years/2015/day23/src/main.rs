@@ -1,226 +1,400 @@
-#[derive(Debug)]
-enum Register {
-    A,
-    B,
-}
+mod analysis;
+mod debug;
+mod optimize;
 
-impl From<&str> for Register {
-    fn from(id: &str) -> Self {
-        match id.chars().next() {
-            Some('a') => Self::A,
-            Some('b') => Self::B,
-            Some(id) => panic!("bad register id : {}", id),
-            _ => panic!("empty string"),
-        }
-    }
-}
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use utils::vm::{
+    Analyze, Branch, Cfg, Flow, Instruction as VmInstruction, Machine, Overflow, Program, Reg,
+    Registers, VmError, Word,
+};
 
 /* ---------- */
 
-#[derive(Debug)]
-enum Offset {
-    Forward(usize),
-    Backward(usize),
-}
+const REG_A: Reg = Reg(0);
+const REG_B: Reg = Reg(1);
+const REGISTER_COUNT: usize = 2;
 
-impl From<&str> for Offset {
-    fn from(off_str: &str) -> Self {
-        let off = off_str[1..]
-            .parse::<usize>()
-            .expect("a valid offset number");
-
-        match off_str.chars().next() {
-            Some('+') => Self::Forward(off),
-            Some('-') => Self::Backward(off),
-            Some(sign) => panic!("bad offset : {}", sign),
-            _ => panic!("empty string"),
-        }
-    }
-}
+/// The puzzle programs halt after a few thousand steps, anything way longer than that is stuck.
+const STEP_LIMIT: u64 = 10_000_000;
 
 /* ---------- */
 
-#[derive(Debug)]
-enum Instruction {
-    Half(Register),
-    Increment(Register),
-    Triple(Register),
-    Jump(Offset),
-    JumpIfEven(Register, Offset),
-    JumpIfOne(Register, Offset),
+#[inline]
+fn parse_offset(off_str: &str) -> Result<isize, String> {
+    off_str
+        .parse::<isize>()
+        .map_err(|_| format!("bad offset : {off_str}"))
 }
 
 /* ---------- */
 
-#[derive(Debug)]
-struct Program(Vec<Instruction>);
-
-impl Program {
-    fn compile(input: &'static str) -> Self {
-        let instructions = input
-            .lines()
-            .map(|line| {
-                let parts = line.split(' ').collect::<Vec<&'static str>>();
-
-                match parts[0] {
-                    "hlf" => Instruction::Half(Register::from(parts[1])),
-                    "inc" => Instruction::Increment(Register::from(parts[1])),
-                    "tpl" => Instruction::Triple(Register::from(parts[1])),
-                    "jmp" => Instruction::Jump(Offset::from(parts[1])),
-                    "jie" => {
-                        Instruction::JumpIfEven(Register::from(parts[1]), Offset::from(parts[2]))
-                    }
-                    "jio" => {
-                        Instruction::JumpIfOne(Register::from(parts[1]), Offset::from(parts[2]))
-                    }
-                    _ => panic!("failed to parse {} as instruction", line),
-                }
-            })
-            .collect();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Half(Reg),
+    Increment(Reg),
+    Triple(Reg),
+    Jump(isize),
+    JumpIfEven(Reg, isize),
+    JumpIfOne(Reg, isize),
+}
 
-        Self(instructions)
-    }
+impl VmInstruction for Instruction {
+    fn parse(line: &str) -> Result<Self, String> {
+        let parts = line.split_ascii_whitespace().collect::<Vec<_>>();
+
+        let instr = match parts.as_slice() {
+            ["hlf", reg] => Self::Half(Reg::parse(reg)?),
+            ["inc", reg] => Self::Increment(Reg::parse(reg)?),
+            ["tpl", reg] => Self::Triple(Reg::parse(reg)?),
+            ["jmp", off] => Self::Jump(parse_offset(off)?),
+            ["jie", reg, off] => Self::JumpIfEven(Reg::parse(reg)?, parse_offset(off)?),
+            ["jio", reg, off] => Self::JumpIfOne(Reg::parse(reg)?, parse_offset(off)?),
+            _ => return Err(format!("failed to parse {line} as instruction")),
+        };
 
-    fn instruction_at(&self, idx: usize) -> Option<&Instruction> {
-        self.0.get(idx)
+        match instr {
+            Self::Half(reg)
+            | Self::Increment(reg)
+            | Self::Triple(reg)
+            | Self::JumpIfEven(reg, _)
+            | Self::JumpIfOne(reg, _)
+                if reg.0 >= REGISTER_COUNT =>
+            {
+                Err(format!("bad register id : {}", reg.name()))
+            }
+            _ => Ok(instr),
+        }
     }
-}
 
-/* ---------- */
+    fn execute(&self, regs: &mut Registers) -> Flow {
+        self.try_execute(regs).expect("no register overflow")
+    }
 
-#[derive(Debug, Default)]
-struct Computer {
-    ra: usize,
-    rb: usize,
-    instr_counter: usize,
-}
+    fn try_execute(&self, regs: &mut Registers) -> Result<Flow, Overflow> {
+        let (reg, val) = match *self {
+            Self::Half(reg) => (reg, Some(regs[reg] / 2)),
+            Self::Increment(reg) => (reg, regs[reg].checked_add(1)),
+            Self::Triple(reg) => (reg, regs[reg].checked_mul(3)),
+            Self::Jump(off) => return Ok(Flow::Jump(off)),
+            Self::JumpIfEven(reg, off) if regs[reg] % 2 == 0 => return Ok(Flow::Jump(off)),
+            Self::JumpIfOne(reg, off) if regs[reg] == 1 => return Ok(Flow::Jump(off)),
+            Self::JumpIfEven(..) | Self::JumpIfOne(..) => return Ok(Flow::Next),
+        };
 
-impl Computer {
-    #[inline(always)]
-    fn new() -> Self {
-        Self::default()
+        regs[reg] = val.ok_or(Overflow(reg))?;
+        Ok(Flow::Next)
     }
+}
 
-    fn reg(&self, reg: Register) -> usize {
-        match reg {
-            Register::A => self.ra,
-            Register::B => self.rb,
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Half(reg) => write!(f, "hlf {}", reg.name()),
+            Self::Increment(reg) => write!(f, "inc {}", reg.name()),
+            Self::Triple(reg) => write!(f, "tpl {}", reg.name()),
+            Self::Jump(off) => write!(f, "jmp {off:+}"),
+            Self::JumpIfEven(reg, off) => write!(f, "jie {}, {off:+}", reg.name()),
+            Self::JumpIfOne(reg, off) => write!(f, "jio {}, {off:+}", reg.name()),
         }
     }
+}
 
-    fn set_reg(&mut self, reg: Register, val: usize) {
-        match reg {
-            Register::A => self.ra = val,
-            Register::B => self.rb = val,
+impl Analyze for Instruction {
+    fn branch(&self) -> Branch {
+        match *self {
+            Self::Half(_) | Self::Increment(_) | Self::Triple(_) => Branch::Next,
+            Self::Jump(off) => Branch::Jump(off),
+            Self::JumpIfEven(_, off) | Self::JumpIfOne(_, off) => Branch::Cond(off),
         }
     }
 
-    fn run(&mut self, prog: &Program) {
-        while let Some(instr) = prog.instruction_at(self.instr_counter) {
-            match instr {
-                Instruction::Half(reg) => self.half(reg),
-                Instruction::Increment(reg) => self.increment(reg),
-                Instruction::Triple(reg) => self.triple(reg),
-                Instruction::Jump(off) => self.jump(off),
-                Instruction::JumpIfEven(reg, off) => self.jump_if_even(reg, off),
-                Instruction::JumpIfOne(reg, off) => self.jump_if_one(reg, off),
-            }
+    fn fmt_target(&self, target: &str) -> String {
+        match self {
+            Self::Jump(_) => format!("jmp {target}"),
+            Self::JumpIfEven(reg, _) => format!("jie {}, {target}", reg.name()),
+            Self::JumpIfOne(reg, _) => format!("jio {}, {target}", reg.name()),
+            _ => self.to_string(),
         }
     }
+}
 
-    fn half(&mut self, reg: &Register) {
-        match reg {
-            Register::A => self.ra /= 2,
-            Register::B => self.rb /= 2,
-        }
+/* ---------- */
 
-        self.instr_counter += 1;
-    }
+fn run<I: VmInstruction>(prog: &Program<I>, a: Word) -> Result<Machine, VmError> {
+    let mut machine = Machine::new(REGISTER_COUNT).with_step_limit(STEP_LIMIT);
 
-    fn increment(&mut self, reg: &Register) {
-        match reg {
-            Register::A => self.ra += 1,
-            Register::B => self.rb += 1,
-        }
+    machine.set_reg(REG_A, a);
+    machine.run_checked(prog)?;
+    Ok(machine)
+}
 
-        self.instr_counter += 1;
-    }
+/* ---------- */
 
-    fn triple(&mut self, reg: &Register) {
-        match reg {
-            Register::A => self.ra *= 3,
-            Register::B => self.rb *= 3,
-        }
+fn part1(prog: &Program<Instruction>) -> Word {
+    let machine = run(prog, 0).expect("a program running to completion");
+    machine.reg(REG_B)
+}
 
-        self.instr_counter += 1;
-    }
+/* ---------- */
 
-    fn jump(&mut self, off: &Offset) {
-        match off {
-            Offset::Forward(val) => self.instr_counter += val,
-            Offset::Backward(val) => self.instr_counter -= val,
-        }
+fn part2(prog: &Program<Instruction>) -> Word {
+    let machine = run(prog, 1).expect("a program running to completion");
+    machine.reg(REG_B)
+}
+
+/* ---------- */
+
+fn bench(prog: &Program<Instruction>) {
+    let optimized = optimize::optimize(prog);
+
+    println!(
+        "[BENCH] {} instructions, {} once optimized",
+        prog.len(),
+        optimized.len()
+    );
+
+    for a in [0, 1] {
+        let plain = run(prog, a).expect("a program running to completion");
+        let fused = run(&optimized, a).expect("a program running to completion");
+
+        assert_eq!(plain.registers(), fused.registers());
+        println!(
+            "[BENCH] a = {a}: {} steps, {} once optimized",
+            plain.steps(),
+            fused.steps()
+        );
     }
 
-    fn jump_if_even(&mut self, reg: &Register, off: &Offset) {
-        let reg_val = match reg {
-            Register::A => self.ra,
-            Register::B => self.rb,
-        };
+    println!("[BENCH] plain interpretation");
+    utils::bench!((0..1_000).for_each(|_| {
+        std::hint::black_box(run(prog, 1).ok());
+    }));
 
-        if reg_val % 2 != 0 {
-            self.instr_counter += 1;
-            return;
-        }
+    println!("[BENCH] optimized interpretation");
+    utils::bench!((0..1_000).for_each(|_| {
+        std::hint::black_box(run(&optimized, 1).ok());
+    }));
+}
 
-        match off {
-            Offset::Forward(val) => self.instr_counter += val,
-            Offset::Backward(val) => self.instr_counter -= val,
-        }
-    }
+/* ---------- */
 
-    fn jump_if_one(&mut self, reg: &Register, off: &Offset) {
-        let reg_val = match reg {
-            Register::A => self.ra,
-            Register::B => self.rb,
-        };
+fn main() {
+    let program = Program::parse(utils::input_str!()).expect("a valid program");
 
-        if reg_val != 1 {
-            self.instr_counter += 1;
-            return;
+    match std::env::args().nth(1).as_deref() {
+        Some("disasm") => {
+            print!("{}", utils::vm::disassemble(&program));
+            println!();
+            print!("{}", analysis::describe_loops(&program));
         }
-
-        match off {
-            Offset::Forward(val) => self.instr_counter += val,
-            Offset::Backward(val) => self.instr_counter -= val,
+        Some("cfg") => print!("{}", Cfg::new(&program).to_dot(&program)),
+        Some("bench") => bench(&program),
+        Some("debug") => {
+            utils::answer!(&program);
+            debug::run(&program).expect("a working stdin/stdout");
         }
+        _ => utils::answer!(&program),
     }
 }
 
 /* ---------- */
 
-fn part1(prog: &Program) -> usize {
-    let mut comp = Computer::new();
+#[cfg(test)]
+mod test {
+    use utils::vm::{
+        Breakpoint, Cfg, Cmp, Debugger, Machine, Program, Stop, Target, VmError, Word,
+    };
 
-    comp.run(prog);
-    comp.reg(Register::B)
-}
+    use crate::analysis::Collatz;
+    use crate::optimize::{optimize, Op};
+    use crate::{run, Instruction, REGISTER_COUNT, REG_A, REG_B};
 
-/* ---------- */
+    #[test]
+    fn test_example() {
+        let prog = Program::<Instruction>::parse("inc a\njio a, +2\ntpl a\ninc a").unwrap();
+        let mut machine = Machine::new(REGISTER_COUNT);
 
-fn part2(prog: &Program) -> usize {
-    let mut comp = Computer::new();
+        machine.run(&prog);
+        assert_eq!(machine.reg(REG_A), 2);
+        assert_eq!(machine.steps(), 3);
+    }
 
-    comp.set_reg(Register::A, 1);
+    #[test]
+    fn test_parse_errors() {
+        let err = Program::<Instruction>::parse("inc a\ninc c").unwrap_err();
+        assert_eq!(err.line, 2);
 
-    comp.run(prog);
-    comp.reg(Register::B)
-}
+        assert!(Program::<Instruction>::parse("jmp x").is_err());
+        assert!(Program::<Instruction>::parse("foo a").is_err());
+    }
 
-/* ---------- */
+    #[test]
+    fn test_debugger() {
+        let prog = Program::<Instruction>::parse("inc a\njio a, +2\ntpl a\ninc a").unwrap();
+        let mut dbg = Debugger::new(&prog, Machine::new(REGISTER_COUNT));
+
+        dbg.record(true);
+        dbg.add_breakpoint(Breakpoint::At(3));
+        dbg.add_breakpoint(Breakpoint::When(REG_A, Cmp::Eq, 1));
+
+        assert_eq!(dbg.resume(), Stop::Breakpoint(1));
+        assert_eq!(dbg.machine().pc(), 1);
+        assert_eq!(dbg.resume(), Stop::Breakpoint(0));
+        assert_eq!(dbg.machine().pc(), 3);
+        assert_eq!(dbg.resume(), Stop::Halted);
+
+        assert_eq!(dbg.counts(), [1, 1, 0, 1]);
+        assert_eq!(
+            dbg.trace().iter().map(|entry| entry.pc).collect::<Vec<_>>(),
+            [0, 1, 3]
+        );
+
+        let mut dump = Vec::new();
+        dbg.write_trace(&mut dump).unwrap();
+        assert_eq!(String::from_utf8(dump).unwrap().lines().count(), 3);
+
+        // Failures and runaway programs stop the debugger instead of panicking or hanging.
+        let prog = Program::<Instruction>::parse("tpl a\njmp -1").unwrap();
+        let mut dbg = Debugger::new(&prog, Machine::new(REGISTER_COUNT).with_step_limit(100));
+
+        dbg.machine_mut().set_reg(REG_A, Word::MAX);
+        assert_eq!(
+            dbg.step(),
+            Err(VmError::RegisterOverflow { pc: 0, reg: REG_A })
+        );
+        assert_eq!(dbg.counts(), [0, 0]);
+
+        dbg.machine_mut().set_reg(REG_A, 0);
+        dbg.record(true);
+        dbg.set_trace_capacity(10);
+        assert_eq!(dbg.resume(), Stop::Error(VmError::StepLimitExceeded(100)));
+        assert_eq!(dbg.counts(), [50, 50]);
+        assert_eq!(dbg.trace().len(), 10);
+        assert_eq!(dbg.trace()[0].step, 90);
+    }
 
-fn main() {
-    let program = Program::compile(utils::input_str!());
+    #[test]
+    fn test_cfg() {
+        let prog = Program::<Instruction>::parse(utils::input_str!()).unwrap();
+        let cfg = Cfg::new(&prog);
+
+        assert_eq!(cfg.blocks()[0].succs.len(), 2);
+        assert!(cfg
+            .blocks()
+            .iter()
+            .any(|block| block.succs.iter().any(|(succ, _)| *succ == Target::Exit)));
+
+        let loops = cfg.loops();
+        assert_eq!(loops.len(), 1);
+        assert_eq!(cfg.blocks()[loops[0].header].start, 39);
+        assert_eq!(
+            Collatz::recognize(&prog, &cfg, &loops[0]),
+            Some(Collatz {
+                value: REG_A,
+                counter: REG_B
+            })
+        );
+
+        let disasm = utils::vm::disassemble(&prog);
+        assert!(disasm.lines().next().unwrap().ends_with("jio a, L0"));
+        assert!(disasm.contains("jio a, end"));
+    }
+
+    #[test]
+    fn test_optimize() {
+        let prog = Program::<Instruction>::parse(utils::input_str!()).unwrap();
+        let optimized = optimize(&prog);
+
+        assert_eq!(optimized.len(), 5);
+        assert!(matches!(optimized[4], Op::Collatz { exit: 1, .. }));
 
-    utils::answer!(&program)
+        for a in 0..64 {
+            let plain = run(&prog, a).unwrap();
+            let fused = run(&optimized, a).unwrap();
+
+            assert_eq!(plain.registers(), fused.registers());
+            assert!(fused.steps() <= 5);
+        }
+
+        // A jump landing in the middle of a run of arithmetic prevents its fusion.
+        let prog = Program::<Instruction>::parse("inc a\ntpl a\njie a, -1\ninc b\ninc b").unwrap();
+        let optimized = optimize(&prog);
+
+        assert_eq!(optimized.len(), 4);
+        assert_eq!(optimized[2], Op::Base(Instruction::JumpIfEven(REG_A, -1)));
+        assert_eq!(
+            run(&prog, 0).unwrap().registers(),
+            run(&optimized, 0).unwrap().registers()
+        );
+
+        // A Collatz loop whose exit lands inside of it is kept as is.
+        let prog = Program::<Instruction>::parse(
+            "jio a, +2\ninc b\njie a, +4\ntpl a\ninc a\njmp +2\nhlf a\njmp -7",
+        )
+        .unwrap();
+        let optimized = optimize(&prog);
+
+        assert!(optimized.iter().all(|op| !matches!(op, Op::Collatz { .. })));
+        for a in 0..8 {
+            let mut machine = Machine::new(REGISTER_COUNT).with_step_limit(1_000);
+
+            machine.set_reg(REG_A, a);
+            assert_eq!(
+                machine.clone().run_checked(&optimized),
+                Err(VmError::StepLimitExceeded(1_000))
+            );
+        }
+
+        // Long runs of arithmetic are split before their coefficients overflow.
+        let prog = Program::<Instruction>::parse(&"tpl a\n".repeat(41)).unwrap();
+        let optimized = optimize(&prog);
+
+        assert_eq!(optimized.len(), 2);
+        assert_eq!(run(&optimized, 0).unwrap().reg(REG_A), 0);
+        assert!(run(&optimized, 1).is_err());
+    }
+
+    #[test]
+    fn test_checked() {
+        let parse = |prog| Program::<Instruction>::parse(prog).unwrap();
+
+        let prog = parse("inc a\njmp -2");
+        assert_eq!(
+            run(&prog, 0).unwrap_err(),
+            VmError::PcOutOfRange { pc: 1, target: -1 }
+        );
+
+        let prog = parse("tpl a");
+        assert_eq!(
+            run(&prog, Word::MAX / 2).unwrap_err(),
+            VmError::RegisterOverflow { pc: 0, reg: REG_A }
+        );
+
+        let prog = parse("jmp +2\ninc a\njmp +1");
+        assert!(run(&prog, 0).is_ok());
+
+        let prog = parse("jmp +2\ninc a\njmp +2");
+        assert!(matches!(
+            run(&prog, 0).unwrap_err(),
+            VmError::PcOutOfRange { pc: 2, target: 4 }
+        ));
+
+        let prog = parse("inc b\njmp -1");
+        let mut machine = Machine::new(REGISTER_COUNT).with_step_limit(100);
+        assert_eq!(
+            machine.run_checked(&prog).unwrap_err(),
+            VmError::StepLimitExceeded(100)
+        );
+        assert_eq!(machine.reg(REG_B), 50);
+
+        // A Collatz loop starting from 0 never ends, fused or not.
+        let prog = parse("jio a, +8\ninc b\njie a, +4\ntpl a\ninc a\njmp +2\nhlf a\njmp -7");
+        let optimized = optimize(&prog);
+        assert_eq!(optimized.len(), 1);
+
+        let mut machine = Machine::new(REGISTER_COUNT).with_step_limit(1_000);
+        assert!(machine.clone().run_checked(&prog).is_err());
+        assert!(machine.run_checked(&optimized).is_err());
+    }
 }
@@ -0,0 +1,272 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use utils::vm::{Flow, Instruction as VmInstruction, Overflow, Program, Reg, Registers, Word};
+
+use crate::Instruction;
+
+/* ---------- */
+
+/// The Collatz loop, as laid out by the puzzle input. `jio` leaves the loop, every other jump
+/// stays in it.
+const COLLATZ_LEN: usize = 8;
+
+/* ---------- */
+
+/// An instruction of an optimized program: either one of the puzzle or a superinstruction
+/// standing for a whole sequence of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Base(Instruction),
+    /// `reg = reg * mul + add`, replaces a run of `inc`/`tpl` on the same register.
+    Affine {
+        reg: Reg,
+        mul: Word,
+        add: Word,
+    },
+    /// Runs a whole Collatz loop, see [`crate::analysis::Collatz`], then jumps out of it.
+    Collatz {
+        value: Reg,
+        counter: Reg,
+        exit: isize,
+    },
+}
+
+impl VmInstruction for Op {
+    fn parse(line: &str) -> Result<Self, String> {
+        Instruction::parse(line).map(Self::Base)
+    }
+
+    fn execute(&self, regs: &mut Registers) -> Flow {
+        self.try_execute(regs).expect("no register overflow")
+    }
+
+    fn try_execute(&self, regs: &mut Registers) -> Result<Flow, Overflow> {
+        match *self {
+            Self::Base(instr) => instr.try_execute(regs),
+            Self::Affine { reg, mul, add } => {
+                regs[reg] = regs[reg]
+                    .checked_mul(mul)
+                    .and_then(|val| val.checked_add(add))
+                    .ok_or(Overflow(reg))?;
+
+                Ok(Flow::Next)
+            }
+            Self::Collatz {
+                value,
+                counter,
+                exit,
+            } => {
+                let (mut val, mut count) = (regs[value], regs[counter]);
+
+                // The sequence never reaches 1 from there, spinning on this very instruction
+                // keeps the loop endless while still counting steps.
+                if val < 1 {
+                    return Ok(Flow::Jump(0));
+                }
+
+                while val != 1 {
+                    count = count.checked_add(1).ok_or(Overflow(counter))?;
+
+                    val = match val % 2 {
+                        0 => val / 2,
+                        _ => val
+                            .checked_mul(3)
+                            .and_then(|val| val.checked_add(1))
+                            .ok_or(Overflow(value))?,
+                    };
+                }
+
+                regs[value] = val;
+                regs[counter] = count;
+                Ok(Flow::Jump(exit))
+            }
+        }
+    }
+}
+
+impl Display for Op {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Base(instr) => write!(f, "{instr}"),
+            Self::Affine { reg, mul, add } => write!(f, "aff {}, {mul}, {add}", reg.name()),
+            Self::Collatz {
+                value,
+                counter,
+                exit,
+            } => write!(f, "clz {}, {}, {exit:+}", value.name(), counter.name()),
+        }
+    }
+}
+
+/* ---------- */
+
+#[inline]
+fn offset(instr: Instruction) -> Option<isize> {
+    match instr {
+        Instruction::Jump(off)
+        | Instruction::JumpIfEven(_, off)
+        | Instruction::JumpIfOne(_, off) => Some(off),
+        _ => None,
+    }
+}
+
+/* ---------- */
+
+#[inline]
+fn with_offset(instr: Instruction, off: isize) -> Instruction {
+    match instr {
+        Instruction::Jump(_) => Instruction::Jump(off),
+        Instruction::JumpIfEven(reg, _) => Instruction::JumpIfEven(reg, off),
+        Instruction::JumpIfOne(reg, _) => Instruction::JumpIfOne(reg, off),
+        instr => instr,
+    }
+}
+
+/* ---------- */
+
+/// Matches a Collatz loop at the start of `instrs`, returning its value and counter registers along
+/// with the offset of its exit.
+fn match_collatz(instrs: &[Instruction]) -> Option<(Reg, Reg, isize)> {
+    use Instruction::*;
+
+    match *instrs.get(..COLLATZ_LEN)? {
+        [JumpIfOne(v0, exit), Increment(c), JumpIfEven(v1, 4), Triple(v2), Increment(v3), Jump(2), Half(v4), Jump(-7)]
+            if c != v0 && [v1, v2, v3, v4].iter().all(|&v| v == v0) =>
+        {
+            Some((v0, c, exit))
+        }
+        _ => None,
+    }
+}
+
+/* ---------- */
+
+/// Returns the length of the run of `inc`/`tpl` on the same register starting at `pc`, along
+/// with the fused affine operation. The run stops before its coefficients would overflow.
+fn match_affine(instrs: &[Instruction], pc: usize) -> Option<(usize, Op)> {
+    let reg = match instrs.get(pc)? {
+        Instruction::Increment(reg) | Instruction::Triple(reg) => *reg,
+        _ => return None,
+    };
+
+    let (mut mul, mut add): (Word, Word) = (1, 0);
+    let mut len = 0;
+
+    for instr in &instrs[pc..] {
+        let next = match *instr {
+            Instruction::Increment(r) if r == reg => add.checked_add(1).map(|add| (mul, add)),
+            Instruction::Triple(r) if r == reg => mul.checked_mul(3).zip(add.checked_mul(3)),
+            _ => break,
+        };
+
+        // The rest of the run is left to the next affine operation rather than overflowing.
+        let Some(next) = next else {
+            break;
+        };
+
+        (mul, add) = next;
+        len += 1;
+    }
+
+    Some((len, Op::Affine { reg, mul, add }))
+}
+
+/* ---------- */
+
+/// Replaces the idioms of the puzzle with superinstructions: runs of arithmetic on the same
+/// register become a single affine operation and Collatz loops a single instruction.
+///
+/// A sequence is only fused if no jump lands in the middle of it. Jump offsets are then
+/// remapped to the new layout of the program.
+pub fn optimize(prog: &Program<Instruction>) -> Program<Op> {
+    let instrs = prog.iter().copied().collect::<Vec<_>>();
+    let len = instrs.len() as isize;
+
+    let jumps_to = instrs
+        .iter()
+        .enumerate()
+        .filter_map(|(pc, &instr)| offset(instr).map(|off| (pc, pc as isize + off)))
+        .collect::<Vec<_>>();
+
+    // Jumps from within the sequence are fused along with it, only the ones coming from the
+    // outside matter.
+    let fusable = |pc: usize, run: usize| {
+        let seq = pc..pc + run;
+        let inner = pc as isize + 1..(pc + run) as isize;
+
+        jumps_to
+            .iter()
+            .all(|(src, target)| seq.contains(src) || !inner.contains(target))
+    };
+
+    // Index of every original instruction in the optimized program.
+    let mut mapping = vec![None; instrs.len()];
+    // Instructions of the optimized program whose jump has to be remapped, with the index of
+    // the original instruction they jumped from and their original offset.
+    let mut jumps = Vec::new();
+    let mut ops = Vec::new();
+    let mut pc = 0;
+
+    while pc < instrs.len() {
+        mapping[pc] = Some(ops.len());
+
+        if let Some((value, counter, exit)) = match_collatz(&instrs[pc..]) {
+            // The fused loop can only jump out of itself, its inner instructions are gone.
+            let leaves = !(0..COLLATZ_LEN as isize).contains(&exit);
+
+            if leaves && fusable(pc, COLLATZ_LEN) {
+                jumps.push((ops.len(), pc, exit));
+                ops.push(Op::Collatz {
+                    value,
+                    counter,
+                    exit,
+                });
+                pc += COLLATZ_LEN;
+                continue;
+            }
+        }
+
+        if let Some((run, op)) = match_affine(&instrs, pc) {
+            if run > 1 && fusable(pc, run) {
+                ops.push(op);
+                pc += run;
+                continue;
+            }
+        }
+
+        if let Some(off) = offset(instrs[pc]) {
+            jumps.push((ops.len(), pc, off));
+        }
+
+        ops.push(Op::Base(instrs[pc]));
+        pc += 1;
+    }
+
+    let new_len = ops.len() as isize;
+
+    for (idx, old_pc, off) in jumps {
+        let old_target = old_pc as isize + off;
+
+        let new_target = match old_target {
+            target if target < 0 => target,
+            target if target >= len => new_len + (target - len),
+            target => {
+                mapping[target as usize].expect("a jump target kept by the optimizer") as isize
+            }
+        };
+
+        let new_off = new_target - idx as isize;
+
+        ops[idx] = match ops[idx] {
+            Op::Base(instr) => Op::Base(with_offset(instr, new_off)),
+            Op::Collatz { value, counter, .. } => Op::Collatz {
+                value,
+                counter,
+                exit: new_off,
+            },
+            op => op,
+        };
+    }
+
+    Program::new(ops)
+}
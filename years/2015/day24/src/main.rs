@@ -1,23 +1,41 @@
+mod qe;
+mod search;
+
+use qe::Qe;
+
 type Result = std::result::Result<(), ()>;
 
 /* ---------- */
 
 #[derive(Debug)]
 struct IdealConfiguration {
-    qe: u64,
+    qe: Qe,
     count: usize,
+    groups: Vec<Vec<u64>>,
 }
 
 impl IdealConfiguration {
     #[inline(always)]
     fn new() -> Self {
         Self {
-            qe: u64::MAX,
+            qe: Qe::from(u64::MAX),
             count: usize::MAX,
+            groups: vec![],
+        }
+    }
+
+    fn from_groups(groups: Vec<Vec<u64>>) -> Self {
+        Self {
+            qe: Qe::of(&groups[0]),
+            count: groups[0].len(),
+            groups,
         }
     }
 
-    fn apply(&mut self, group: &Group) {
+    /// Keeps `groups` if its first group, the one in the passenger compartment, has fewer
+    /// packages than the current best, or as many with a lower quantum entanglement.
+    fn apply(&mut self, groups: &[Group]) {
+        let group = &groups[0];
         let grp_count = group.count();
 
         if self.count < grp_count {
@@ -26,17 +44,33 @@ impl IdealConfiguration {
 
         let grp_qe = group.qe();
 
-        if self.count > grp_count {
+        if self.count > grp_count || self.qe > grp_qe {
             self.count = grp_count;
             self.qe = grp_qe;
-        } else if self.count == grp_count && self.qe > grp_qe {
-            self.qe = grp_qe;
+            self.groups = groups.iter().map(|group| group.items.clone()).collect();
         }
     }
 
     #[inline(always)]
-    fn qe(&self) -> u64 {
-        self.qe
+    fn qe(&self) -> &Qe {
+        &self.qe
+    }
+
+    #[inline(always)]
+    fn groups(&self) -> &[Vec<u64>] {
+        &self.groups
+    }
+}
+
+impl std::fmt::Display for IdealConfiguration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let groups = self
+            .groups
+            .iter()
+            .map(|group| format!("{group:?}"))
+            .collect::<Vec<_>>();
+
+        write!(f, "{} ({})", self.qe(), groups.join(" | "))
     }
 }
 
@@ -63,8 +97,8 @@ impl Group {
         self.items.len()
     }
 
-    fn qe(&self) -> u64 {
-        self.items.iter().product()
+    fn qe(&self) -> Qe {
+        Qe::of(&self.items)
     }
 
     fn push(&mut self, item: u64) -> Result {
@@ -87,53 +121,82 @@ impl Group {
 
 /* ---------- */
 
-fn get_all_configurations(pkgs: &[u64]) -> IdealConfiguration {
+/// Splits the packages into `compartments` groups of equal weight, returning the split whose
+/// first group is the smallest then the least entangled. `None` if no such split exists.
+fn get_all_configurations(pkgs: &[u64], compartments: usize) -> Option<IdealConfiguration> {
     fn rec_find_configs(
         pkgs: &[u64],
         idx: usize,
-        g1: &mut Group,
-        g2: &mut Group,
-        g3: &mut Group,
+        groups: &mut [Group],
         conf: &mut IdealConfiguration,
     ) {
         if idx == 0 {
-            conf.apply(g1);
+            conf.apply(groups);
             return;
         }
 
-        if g1.push(pkgs[idx - 1]).is_ok() {
-            rec_find_configs(pkgs, idx - 1, g1, g2, g3, conf);
-            g1.pop();
-        }
-
-        if g2.push(pkgs[idx - 1]).is_ok() {
-            rec_find_configs(pkgs, idx - 1, g1, g2, g3, conf);
-            g2.pop();
-        }
-
-        if g3.push(pkgs[idx - 1]).is_ok() {
-            rec_find_configs(pkgs, idx - 1, g1, g2, g3, conf);
-            g3.pop();
+        for g in 0..groups.len() {
+            // The first group can't become the ideal one anymore.
+            if g == 0 && groups[0].count() >= conf.count {
+                continue;
+            }
+
+            // The other groups are interchangeable, only fill the first empty one.
+            if g > 1 && groups[g].count() == 0 && groups[g - 1].count() == 0 {
+                break;
+            }
+
+            if groups[g].push(pkgs[idx - 1]).is_ok() {
+                rec_find_configs(pkgs, idx - 1, groups, conf);
+                groups[g].pop();
+            }
         }
     }
 
     let total_weight = pkgs.iter().sum::<u64>();
 
-    if total_weight % 3 != 0 {
-        panic!("problem can't be solved")
+    if compartments == 0 || total_weight % compartments as u64 != 0 {
+        return None;
     }
 
     let len = pkgs.len();
-    let target_weight = total_weight / 3;
-    let mut g1 = Group::new(len, target_weight);
-    let mut g2 = Group::new(len, target_weight);
-    let mut g3 = Group::new(len, target_weight);
+    let target_weight = total_weight / compartments as u64;
+    let mut groups = (0..compartments)
+        .map(|_| Group::new(len, target_weight))
+        .collect::<Vec<_>>();
 
     let mut conf = IdealConfiguration::new();
 
-    rec_find_configs(pkgs, len, &mut g1, &mut g2, &mut g3, &mut conf);
+    rec_find_configs(pkgs, len, &mut groups, &mut conf);
+
+    (!conf.groups().is_empty()).then_some(conf)
+}
+
+/* ---------- */
 
-    conf
+fn part1(pkgs: &[u64]) -> IdealConfiguration {
+    search::balance(pkgs, 3).expect("packages splitting in three")
+}
+
+/* ---------- */
+
+fn part2(pkgs: &[u64]) -> IdealConfiguration {
+    search::balance(pkgs, 4).expect("packages splitting in four")
+}
+
+/* ---------- */
+
+fn bench(pkgs: &[u64]) {
+    for compartments in [3, 4] {
+        println!("[BENCH] {compartments} compartments, exhaustive recursion");
+        utils::bench!(std::hint::black_box(get_all_configurations(
+            pkgs,
+            compartments
+        )));
+
+        println!("[BENCH] {compartments} compartments, smallest first search");
+        utils::bench!(std::hint::black_box(search::balance(pkgs, compartments)));
+    }
 }
 
 /* ---------- */
@@ -144,7 +207,91 @@ fn main() {
         .map(|line| line.parse().unwrap())
         .collect::<Vec<_>>();
 
-    let conf = get_all_configurations(&pkgs);
+    match std::env::args().nth(1).as_deref() {
+        Some("bench") => bench(&pkgs),
+        _ => utils::answer!(&pkgs),
+    }
+}
+
+/* ---------- */
+
+#[cfg(test)]
+mod test {
+    use crate::qe::Qe;
+    use crate::search::balance;
+    use crate::{get_all_configurations, part1, part2};
+
+    const PKGS: [u64; 10] = [1, 2, 3, 4, 5, 7, 8, 9, 10, 11];
+
+    #[test]
+    fn test_part1() {
+        let conf = part1(&PKGS);
+
+        assert_eq!(conf.qe(), &Qe::from(99));
+        assert_eq!(conf.groups()[0], [11, 9]);
+        assert!(conf.groups().iter().all(|g| g.iter().sum::<u64>() == 20));
+    }
+
+    #[test]
+    fn test_part2() {
+        let conf = part2(&PKGS);
+
+        assert_eq!(conf.qe(), &Qe::from(44));
+        assert_eq!(conf.groups().len(), 4);
+    }
 
-    println!("result = {}", conf.qe())
+    #[test]
+    fn test_unsolvable() {
+        assert!(get_all_configurations(&[1, 2, 4], 2).is_none());
+        assert!(get_all_configurations(&[1, 2, 5], 2).is_none());
+    }
+
+    #[test]
+    fn test_searches_agree() {
+        let pkgs = [1, 3, 5, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43];
+
+        for compartments in [2, 3, 4] {
+            let exhaustive =
+                get_all_configurations(&pkgs, compartments).map(|conf| conf.qe().clone());
+            let fast = balance(&pkgs, compartments).map(|conf| conf.qe().clone());
+
+            assert_eq!(exhaustive, fast);
+        }
+
+        assert_eq!(balance(&PKGS, 3).unwrap().groups()[0], [11, 9]);
+        assert!(balance(&[1, 2, 5], 2).is_none());
+    }
+
+    #[test]
+    fn test_large_weights() {
+        let conf = balance(&[1 << 40, 1 << 40, 1 << 40], 3).unwrap();
+
+        assert_eq!(conf.groups()[0], [1 << 40]);
+        assert_eq!(conf.qe(), &Qe::from(1 << 40));
+
+        let conf = balance(&[u64::MAX; 4], 2).unwrap();
+
+        assert_eq!(conf.groups()[0], [u64::MAX, u64::MAX]);
+        assert!(balance(&[u64::MAX, u64::MAX, u64::MAX], 2).is_none());
+    }
+
+    #[test]
+    fn test_qe_overflow() {
+        let big = Qe::of(&[u64::MAX, 3]);
+        let wide = 1u128 << 40;
+
+        assert!(matches!(big, Qe::Big(_)));
+        assert!(big > Qe::from(u64::MAX));
+        assert!(big < Qe::of(&[u64::MAX, 4]));
+        assert_eq!(big.to_string(), (u64::MAX as u128 * 3).to_string());
+        assert_eq!(
+            Qe::of(&[1 << 40, 1 << 40, 7]).to_string(),
+            (wide * wide * 7).to_string()
+        );
+        assert_eq!(
+            Qe::of(&[u64::MAX, u64::MAX, u64::MAX]).to_string(),
+            "6277101735386680762814942322444851025767571854389858533375"
+        );
+        assert_eq!(Qe::of(&[1 << 32, 1 << 31, 0]), Qe::from(0));
+    }
 }
@@ -0,0 +1,112 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/* ---------- */
+
+/// Quantum entanglement of a group of packages. Stays a plain `u64` as long as the product fits
+/// and widens to an arbitrary precision integer otherwise, so comparisons never see a wrapped
+/// value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Qe {
+    Small(u64),
+    /// Little endian 32 bits limbs of a value above `u64::MAX`, without trailing zero limbs.
+    Big(Vec<u32>),
+}
+
+impl Qe {
+    pub fn of(items: &[u64]) -> Self {
+        items.iter().fold(Self::Small(1), |qe, &item| qe.mul(item))
+    }
+
+    fn mul(self, factor: u64) -> Self {
+        let limbs = match self {
+            Self::Small(value) => match value.checked_mul(factor) {
+                Some(product) => return Self::Small(product),
+                None => vec![value as u32, (value >> 32) as u32],
+            },
+            Self::Big(limbs) => limbs,
+        };
+        let mut product = Vec::with_capacity(limbs.len() + 2);
+        let mut carry = 0u128;
+
+        for limb in limbs {
+            carry += limb as u128 * factor as u128;
+            product.push(carry as u32);
+            carry >>= 32;
+        }
+
+        while carry > 0 {
+            product.push(carry as u32);
+            carry >>= 32;
+        }
+
+        while product.last() == Some(&0) {
+            product.pop();
+        }
+
+        match product.len() {
+            0..=2 => Self::Small(product.iter().rev().fold(0, |acc, &l| acc << 32 | l as u64)),
+            _ => Self::Big(product),
+        }
+    }
+}
+
+impl From<u64> for Qe {
+    fn from(value: u64) -> Self {
+        Self::Small(value)
+    }
+}
+
+impl Ord for Qe {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Small(a), Self::Small(b)) => a.cmp(b),
+            (Self::Small(_), Self::Big(_)) => Ordering::Less,
+            (Self::Big(_), Self::Small(_)) => Ordering::Greater,
+            (Self::Big(a), Self::Big(b)) => a
+                .len()
+                .cmp(&b.len())
+                .then_with(|| a.iter().rev().cmp(b.iter().rev())),
+        }
+    }
+}
+
+impl PartialOrd for Qe {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for Qe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        const CHUNK: u64 = 1_000_000_000;
+
+        let mut limbs = match self {
+            Self::Small(value) => return write!(f, "{value}"),
+            Self::Big(limbs) => limbs.clone(),
+        };
+        let mut chunks = vec![];
+
+        while !limbs.is_empty() {
+            let mut rem = 0u64;
+
+            for limb in limbs.iter_mut().rev() {
+                let cur = rem << 32 | *limb as u64;
+
+                *limb = (cur / CHUNK) as u32;
+                rem = cur % CHUNK;
+            }
+
+            chunks.push(rem);
+
+            while limbs.last() == Some(&0) {
+                limbs.pop();
+            }
+        }
+
+        let mut chunks = chunks.into_iter().rev();
+
+        write!(f, "{}", chunks.next().unwrap_or(0))?;
+        chunks.try_for_each(|chunk| write!(f, "{chunk:09}"))
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+
+use crate::{IdealConfiguration, Qe};
+
+/* ---------- */
+
+/// Every sum reachable with some of `pkgs`, capped to `target`.
+fn sums_up_to(pkgs: &[u64], target: u128) -> HashSet<u128> {
+    let mut sums = HashSet::from([0]);
+
+    for &pkg in pkgs {
+        let more = sums
+            .iter()
+            .map(|&sum| sum + pkg as u128)
+            .filter(|&sum| sum <= target)
+            .collect::<Vec<_>>();
+
+        sums.extend(more);
+    }
+
+    sums
+}
+
+/// Whether some of `pkgs` add up to exactly `target`. Meets in the middle so the work depends on
+/// the number of packages, not on their weight.
+fn reachable(pkgs: &[u64], target: u128) -> bool {
+    let (left, right) = pkgs.split_at(pkgs.len() / 2);
+    let left = sums_up_to(left, target);
+
+    sums_up_to(right, target)
+        .into_iter()
+        .any(|sum| left.contains(&(target - sum)))
+}
+
+/// Calls `f` with every subset of `pkgs` of exactly `size` packages weighting `target`, until it
+/// returns `true`. `pkgs` is expected sorted in decreasing order.
+fn subsets(pkgs: &[u64], size: usize, target: u128, f: &mut impl FnMut(&[u64]) -> bool) -> bool {
+    fn rec(
+        pkgs: &[u64],
+        size: usize,
+        target: u128,
+        picked: &mut Vec<u64>,
+        f: &mut impl FnMut(&[u64]) -> bool,
+    ) -> bool {
+        if picked.len() == size {
+            return target == 0 && f(picked);
+        }
+
+        let missing = size - picked.len();
+
+        for (i, &pkg) in pkgs.iter().enumerate() {
+            if pkgs.len() - i < missing {
+                break;
+            }
+
+            // Packages are decreasing, the heaviest picks can't reach the target anymore.
+            if total(&pkgs[i..i + missing]) < target {
+                break;
+            }
+
+            if pkg as u128 > target {
+                continue;
+            }
+
+            picked.push(pkg);
+            let found = rec(&pkgs[i + 1..], size, target - pkg as u128, picked, f);
+            picked.pop();
+
+            if found {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    rec(pkgs, size, target, &mut Vec::with_capacity(size), f)
+}
+
+/// Total weight of `pkgs`, wide enough to never overflow.
+fn total(pkgs: &[u64]) -> u128 {
+    pkgs.iter().map(|&pkg| pkg as u128).sum()
+}
+
+/// Removes the packages of `group` from `pkgs`, one occurrence each.
+fn without(pkgs: &[u64], group: &[u64]) -> Vec<u64> {
+    let mut rest = pkgs.to_vec();
+
+    for pkg in group {
+        let index = rest
+            .iter()
+            .position(|p| p == pkg)
+            .expect("a package of the group");
+        rest.remove(index);
+    }
+
+    rest
+}
+
+/// Splits `pkgs` into `count` groups weighting `target` each, if possible.
+fn split(pkgs: &[u64], count: usize, target: u128) -> Option<Vec<Vec<u64>>> {
+    if count == 1 {
+        return (total(pkgs) == target).then(|| vec![pkgs.to_vec()]);
+    }
+
+    if !reachable(pkgs, target) {
+        return None;
+    }
+
+    let mut groups = None;
+
+    (1..=pkgs.len()).any(|size| {
+        subsets(pkgs, size, target, &mut |group| {
+            groups = split(&without(pkgs, group), count - 1, target).map(|mut groups| {
+                groups.insert(0, group.to_vec());
+                groups
+            });
+            groups.is_some()
+        })
+    });
+
+    groups
+}
+
+/* ---------- */
+
+/// Tries the candidate first groups by increasing size, then by increasing quantum
+/// entanglement, and keeps the first one leaving packages that can still be balanced over the
+/// other compartments.
+pub fn balance(pkgs: &[u64], compartments: usize) -> Option<IdealConfiguration> {
+    let total_weight = total(pkgs);
+
+    if compartments == 0 || !total_weight.is_multiple_of(compartments as u128) {
+        return None;
+    }
+
+    let target = total_weight / compartments as u128;
+    let mut pkgs = pkgs.to_vec();
+
+    pkgs.sort_unstable_by(|a, b| b.cmp(a));
+
+    (1..=pkgs.len()).find_map(|size| {
+        let mut candidates = vec![];
+
+        subsets(&pkgs, size, target, &mut |group| {
+            candidates.push(group.to_vec());
+            false
+        });
+        candidates.sort_by_cached_key(|group| Qe::of(group));
+
+        candidates.into_iter().find_map(|group| {
+            let mut groups = split(&without(&pkgs, &group), compartments - 1, target)?;
+
+            groups.insert(0, group);
+            Some(IdealConfiguration::from_groups(groups))
+        })
+    })
+}
@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+/* ---------- */
+
 const COL: usize = 3075;
 const ROW: usize = 2981;
 const FIRST_CODE: usize = 20151125;
@@ -7,15 +11,8 @@ const MOD_BY: usize = 33554393;
 
 /* ---------- */
 
-const fn int_sum(mut n: usize) -> usize {
-    let mut sum = 0;
-
-    while n > 0 {
-        sum += n;
-        n -= 1;
-    }
-
-    sum
+const fn int_sum(n: usize) -> usize {
+    n * (n + 1) / 2
 }
 
 /* ---------- */
@@ -25,29 +22,139 @@ const fn grid_to_idx(col: usize, row: usize) -> usize {
     int_sum(line_num - 1) + col
 }
 
+/// Inverse of `grid_to_idx`, returns the `(col, row)` of the code at `idx`.
+const fn idx_to_grid(idx: usize) -> (usize, usize) {
+    // Smallest diagonal whose last index reaches `idx`.
+    let mut line_num = ((8 * idx + 1).isqrt() - 1) / 2;
+
+    if int_sum(line_num) < idx {
+        line_num += 1;
+    }
+
+    let col = idx - int_sum(line_num - 1);
+    (col, line_num + 1 - col)
+}
+
 /* ---------- */
 
+const fn mod_pow(mut base: usize, mut exp: usize) -> usize {
+    let mut res = 1;
+
+    base %= MOD_BY;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = res * base % MOD_BY;
+        }
+
+        base = base * base % MOD_BY;
+        exp >>= 1;
+    }
+
+    res
+}
+
 const fn get_code_at_index(idx: usize) -> usize {
-    let mut val = FIRST_CODE;
+    FIRST_CODE * mod_pow(MULT_BY, idx - 1) % MOD_BY
+}
+
+/// Index of the first occurrence of `code` in the sequence, found by solving
+/// `MULT_BY ^ (idx - 1) = code / FIRST_CODE` with baby-step giant-step. `MOD_BY` being prime,
+/// the division is a multiplication by `FIRST_CODE ^ (MOD_BY - 2)`.
+fn find_code_index(code: usize) -> Option<usize> {
+    if code == 0 || code >= MOD_BY {
+        return None;
+    }
+
+    let target = code * mod_pow(FIRST_CODE, MOD_BY - 2) % MOD_BY;
+    let step = (MOD_BY - 1).isqrt() + 1;
+    let mut baby_steps = HashMap::with_capacity(step);
+    let mut val = 1;
 
-    if idx == 1 {
-        return val;
+    for j in 0..step {
+        baby_steps.entry(val).or_insert(j);
+        val = val * MULT_BY % MOD_BY;
     }
 
-    let mut n = 2;
-    while n <= idx {
-        val = (val * MULT_BY) % MOD_BY;
-        n += 1;
+    // Multiplying by MULT_BY ^ -step walks the target back one giant step at a time.
+    let giant_step = mod_pow(mod_pow(MULT_BY, MOD_BY - 2), step);
+    let mut val = target;
+
+    for i in 0..step {
+        if let Some(j) = baby_steps.get(&val) {
+            return Some(i * step + j + 1);
+        }
+
+        val = val * giant_step % MOD_BY;
     }
 
-    val
+    None
 }
 
 /* ---------- */
 
 fn main() {
-    let idx = grid_to_idx(COL, ROW);
-    let val = get_code_at_index(idx);
+    match std::env::args().nth(1).as_deref() {
+        Some("find") => {
+            let code = std::env::args()
+                .nth(2)
+                .and_then(|code| code.parse().ok())
+                .expect("a code to look for");
+
+            match find_code_index(code) {
+                Some(idx) => {
+                    let (col, row) = idx_to_grid(idx);
+                    println!("{code} is code #{idx}, at row {row}, column {col}");
+                }
+                None => println!("{code} never shows up"),
+            }
+        }
+        _ => {
+            let idx = grid_to_idx(COL, ROW);
+            let val = get_code_at_index(idx);
+
+            println!("result = {}", val);
+        }
+    }
+}
+
+/* ---------- */
 
-    println!("result = {}", val);
+#[cfg(test)]
+mod test {
+    use crate::{find_code_index, get_code_at_index, grid_to_idx, idx_to_grid};
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(get_code_at_index(grid_to_idx(1, 1)), 20151125);
+        assert_eq!(get_code_at_index(grid_to_idx(1, 2)), 31916031);
+        assert_eq!(get_code_at_index(grid_to_idx(2, 1)), 18749137);
+        assert_eq!(get_code_at_index(grid_to_idx(6, 6)), 27995004);
+        assert_eq!(get_code_at_index(grid_to_idx(3, 5)), 28094349);
+    }
+
+    #[test]
+    fn test_grid() {
+        assert_eq!(grid_to_idx(1, 1), 1);
+        assert_eq!(grid_to_idx(2, 1), 3);
+        assert_eq!(grid_to_idx(1, 4), 7);
+
+        for idx in 1..1_000 {
+            let (col, row) = idx_to_grid(idx);
+            assert_eq!(grid_to_idx(col, row), idx);
+        }
+    }
+
+    #[test]
+    fn test_inverse() {
+        assert_eq!(find_code_index(20151125), Some(1));
+        assert_eq!(find_code_index(27995004), Some(grid_to_idx(6, 6)));
+        assert_eq!(find_code_index(0), None);
+
+        // The sequence cycles before reaching the puzzle index, the first occurrence comes back.
+        let idx = grid_to_idx(3075, 2981);
+        let first = find_code_index(get_code_at_index(idx)).unwrap();
+
+        assert!(first <= idx);
+        assert_eq!(get_code_at_index(first), get_code_at_index(idx));
+    }
 }
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::{Instr, Program};
+
+/* ---------- */
+
+/// Rust expression computing `instr`. Shifts go through `checked_shl`/`checked_shr` so that the
+/// generated code agrees with [`Instr::eval`] on shifts of 16 bits or more.
+fn rust_expr(instr: Instr) -> String {
+    match instr {
+        Instr::LeftShift(left, right) => {
+            format!("u16::checked_shl({left}, {right} as u32).unwrap_or(0)")
+        }
+        Instr::RightShift(left, right) => {
+            format!("u16::checked_shr({left}, {right} as u32).unwrap_or(0)")
+        }
+        instr => instr.to_string(),
+    }
+}
+
+/* ---------- */
+
+impl Program {
+    /// Emits a standalone Rust function named `name` that returns the signal of the wire
+    /// `output`.
+    ///
+    /// Only the instructions `output` depends on are emitted.
+    pub fn to_rust(&self, name: &str, output: &str) -> Option<String> {
+        let out = self.register(output)?;
+        let instrs = self.instrs();
+        let live = self.live(out);
+
+        let names = self
+            .wires()
+            .map(|(id, reg)| (reg, id))
+            .collect::<HashMap<_, _>>();
+
+        let mut code = String::new();
+        writeln!(code, "pub fn {name}() -> u16 {{").ok()?;
+
+        for (reg, instr) in instrs.iter().enumerate().filter(|(reg, _)| live[*reg]) {
+            write!(code, "    let r{reg}: u16 = {};", rust_expr(*instr)).ok()?;
+
+            if let Some(id) = names.get(&reg) {
+                write!(code, " // {id}").ok()?;
+            }
+
+            code.push('\n');
+        }
+
+        writeln!(code, "    r{out}\n}}").ok()?;
+        Some(code)
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{Instr, Operand, Program};
+use crate::Wires;
+
+/* ---------- */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileError {
+    /// The wire has nothing plugged in.
+    Undriven(String),
+    /// The wire ends up depending on its own signal.
+    Cycle(String),
+}
+
+/* ---------- */
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    InProgress,
+    Done,
+}
+
+/* ---------- */
+
+/// Lowers the [`crate::Input`] trees of a circuit into a flat [`Program`].
+///
+/// Wires are compiled in dependency order, so by the time a wire is lowered, every wire it reads
+/// from already has a register.
+#[derive(Default)]
+pub struct Compiler {
+    instrs: Vec<Instr>,
+    wires: HashMap<Rc<str>, usize>,
+}
+
+impl Compiler {
+    /// Appends `instr` to the program, returning the register it writes.
+    #[inline]
+    pub fn emit(&mut self, instr: Instr) -> Operand {
+        self.instrs.push(instr);
+        Operand::Reg(self.instrs.len() - 1)
+    }
+
+    /// Returns the register holding the signal of the wire `id`.
+    pub fn wire(&self, id: &str) -> Result<Operand, CompileError> {
+        self.wires
+            .get(id)
+            .map(|&reg| Operand::Reg(reg))
+            .ok_or_else(|| CompileError::Cycle(id.to_string()))
+    }
+
+    pub fn compile(wires: &Wires) -> Result<Program, CompileError> {
+        let mut compiler = Self::default();
+        let mut marks = HashMap::<Rc<str>, Mark>::new();
+
+        for root in wires.iter() {
+            let mut stack = vec![root.clone()];
+
+            while let Some(wire) = stack.last().cloned() {
+                match marks.get(wire.id()) {
+                    Some(Mark::Done) => {
+                        stack.pop();
+                    }
+                    Some(Mark::InProgress) => {
+                        stack.pop();
+
+                        let first = compiler.instrs.len();
+                        let op = wire.lower_driver(&mut compiler)?;
+                        let reg = match op {
+                            Operand::Reg(reg) if reg >= first => reg,
+                            _ => match compiler.emit(Instr::Copy(op)) {
+                                Operand::Reg(reg) => reg,
+                                Operand::Imm(_) => unreachable!(),
+                            },
+                        };
+
+                        compiler.wires.insert(Rc::from(wire.id()), reg);
+                        marks.insert(Rc::from(wire.id()), Mark::Done);
+                    }
+                    None => {
+                        marks.insert(Rc::from(wire.id()), Mark::InProgress);
+
+                        for src in wire.sources() {
+                            match marks.get(src.id()) {
+                                Some(Mark::InProgress) => {
+                                    return Err(CompileError::Cycle(src.id().to_string()))
+                                }
+                                Some(Mark::Done) => (),
+                                None => stack.push(src),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(Program::new(compiler.instrs, compiler.wires))
+    }
+}
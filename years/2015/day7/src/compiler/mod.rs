@@ -0,0 +1,8 @@
+mod codegen;
+mod compile;
+mod program;
+mod solve;
+
+pub use compile::*;
+pub use program::*;
+pub use solve::*;
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result};
+use std::rc::Rc;
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Reg(usize),
+    Imm(u16),
+}
+
+impl Operand {
+    #[inline]
+    fn value(self, regs: &[u16]) -> u16 {
+        match self {
+            Self::Reg(reg) => regs[reg],
+            Self::Imm(value) => value,
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Reg(reg) => write!(f, "r{reg}"),
+            Self::Imm(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/* ---------- */
+
+/// Shifting every bit out of a signal leaves it at 0, instead of overflowing.
+#[inline]
+fn shift(value: u16, amount: u16, op: fn(u16, u32) -> Option<u16>) -> u16 {
+    op(value, amount as u32).unwrap_or(0)
+}
+
+/* ---------- */
+
+/// A single instruction of a compiled circuit. The `n`-th instruction of a program writes the
+/// register `n`, and only ever reads registers written before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instr {
+    Copy(Operand),
+    Not(Operand),
+    And(Operand, Operand),
+    Or(Operand, Operand),
+    LeftShift(Operand, Operand),
+    RightShift(Operand, Operand),
+}
+
+impl Instr {
+    #[inline]
+    pub fn eval(self, regs: &[u16]) -> u16 {
+        match self {
+            Self::Copy(op) => op.value(regs),
+            Self::Not(op) => !op.value(regs),
+            Self::And(left, right) => left.value(regs) & right.value(regs),
+            Self::Or(left, right) => left.value(regs) | right.value(regs),
+            Self::LeftShift(left, right) => {
+                shift(left.value(regs), right.value(regs), u16::checked_shl)
+            }
+            Self::RightShift(left, right) => {
+                shift(left.value(regs), right.value(regs), u16::checked_shr)
+            }
+        }
+    }
+
+    pub fn operands(self) -> Vec<Operand> {
+        match self {
+            Self::Copy(op) | Self::Not(op) => vec![op],
+            Self::And(left, right)
+            | Self::Or(left, right)
+            | Self::LeftShift(left, right)
+            | Self::RightShift(left, right) => vec![left, right],
+        }
+    }
+}
+
+impl Display for Instr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Copy(op) => write!(f, "{op}"),
+            Self::Not(op) => write!(f, "!{op}"),
+            Self::And(left, right) => write!(f, "{left} & {right}"),
+            Self::Or(left, right) => write!(f, "{left} | {right}"),
+            Self::LeftShift(left, right) => write!(f, "{left} << {right}"),
+            Self::RightShift(left, right) => write!(f, "{left} >> {right}"),
+        }
+    }
+}
+
+/* ---------- */
+
+/// A circuit lowered to straight-line code over a register array, see [`crate::Compiler`].
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    instrs: Vec<Instr>,
+    wires: HashMap<Rc<str>, usize>,
+}
+
+impl Program {
+    #[inline]
+    pub(super) fn new(instrs: Vec<Instr>, wires: HashMap<Rc<str>, usize>) -> Self {
+        Self { instrs, wires }
+    }
+
+    #[inline]
+    pub fn instrs(&self) -> &[Instr] {
+        &self.instrs
+    }
+
+    /// Returns the register holding the signal of the wire `id`.
+    #[inline]
+    pub fn register(&self, id: &str) -> Option<usize> {
+        self.wires.get(id).copied()
+    }
+
+    /// Iterates over every wire along with the register holding its signal.
+    #[inline]
+    pub fn wires(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.wires.iter().map(|(id, &reg)| (id.as_ref(), reg))
+    }
+
+    /// Flags every register the register `out` depends on, `out` included. Registers are only
+    /// read after being written, so a single backward pass is enough.
+    pub fn live(&self, out: usize) -> Vec<bool> {
+        let mut live = vec![false; self.instrs.len()];
+        live[out] = true;
+
+        for reg in (0..=out).rev() {
+            if !live[reg] {
+                continue;
+            }
+
+            for op in self.instrs[reg].operands() {
+                if let Operand::Reg(src) = op {
+                    live[src] = true;
+                }
+            }
+        }
+
+        live
+    }
+
+    /// Runs every instruction, returning the content of all the registers.
+    pub fn run(&self) -> Vec<u16> {
+        let mut regs = vec![0; self.instrs.len()];
+        self.run_into(&mut regs);
+        regs
+    }
+
+    /// Same as [`Program::run`] but reuses `regs`, which must hold one slot per instruction.
+    #[inline]
+    pub fn run_into(&self, regs: &mut [u16]) {
+        for (idx, instr) in self.instrs.iter().enumerate() {
+            regs[idx] = instr.eval(regs);
+        }
+    }
+
+    /// Runs the program and returns the signal of the wire `id`.
+    #[inline]
+    pub fn eval(&self, id: &str) -> Option<u16> {
+        let reg = self.register(id)?;
+        Some(self.run()[reg])
+    }
+}
@@ -0,0 +1,120 @@
+use super::{Operand, Program};
+
+/* ---------- */
+
+/// Searches every assignment of a set of free wires that puts a given signal on a target wire.
+///
+/// The search enumerates the whole 16-bit space of each free wire, re-running only the
+/// instructions that both depend on a free wire and feed the target. One free wire is
+/// instantaneous, two are a matter of minutes and more than that is out of reach.
+pub struct Solver<'a> {
+    program: &'a Program,
+    free: Vec<usize>,
+    target: usize,
+    hot: Vec<usize>,
+}
+
+impl<'a> Solver<'a> {
+    /// Returns `None` if one of the wires isn't part of the program.
+    pub fn new(program: &'a Program, target: &str, free: &[&str]) -> Option<Self> {
+        let target = program.register(target)?;
+        let free = free
+            .iter()
+            .map(|id| program.register(id))
+            .collect::<Option<Vec<_>>>()?;
+
+        let instrs = program.instrs();
+        let live = program.live(target);
+
+        let mut dirty = vec![false; instrs.len()];
+        free.iter().for_each(|&reg| dirty[reg] = true);
+
+        let mut hot = Vec::new();
+
+        for (reg, instr) in instrs.iter().enumerate() {
+            if dirty[reg] {
+                continue;
+            }
+
+            let reads_dirty = instr
+                .operands()
+                .into_iter()
+                .any(|op| matches!(op, Operand::Reg(src) if dirty[src]));
+
+            if reads_dirty {
+                dirty[reg] = true;
+
+                if live[reg] {
+                    hot.push(reg);
+                }
+            }
+        }
+
+        Some(Self {
+            program,
+            free,
+            target,
+            hot,
+        })
+    }
+
+    /// Lazily walks every assignment of the free wires, in the order they were given, for which
+    /// the target wire carries `value`. An empty iterator means the target can't be reached.
+    pub fn solve(&self, value: u16) -> Solutions<'_> {
+        Solutions {
+            solver: self,
+            value,
+            regs: self.program.run(),
+            assignment: Some(vec![0u16; self.free.len()]),
+        }
+    }
+}
+
+/* ---------- */
+
+/// The assignments found by [`Solver::solve`], the last free wire changing the fastest.
+pub struct Solutions<'a> {
+    solver: &'a Solver<'a>,
+    value: u16,
+    regs: Vec<u16>,
+    /// The next assignment to try, `None` once the whole space was searched.
+    assignment: Option<Vec<u16>>,
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = Vec<u16>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let solver = self.solver;
+        let instrs = solver.program.instrs();
+
+        while let Some(assignment) = self.assignment.as_mut() {
+            for (&reg, &val) in solver.free.iter().zip(assignment.iter()) {
+                self.regs[reg] = val;
+            }
+
+            for &reg in &solver.hot {
+                self.regs[reg] = instrs[reg].eval(&self.regs);
+            }
+
+            let found = (self.regs[solver.target] == self.value).then(|| assignment.clone());
+
+            // Odometer-style increment, the last free wire being the fastest one.
+            let carried = assignment.iter_mut().rev().all(|val| {
+                let (next, overflow) = val.overflowing_add(1);
+                *val = next;
+                overflow
+            });
+
+            if carried {
+                self.assignment = None;
+            }
+
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        None
+    }
+}
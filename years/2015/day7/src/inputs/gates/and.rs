@@ -1,4 +1,5 @@
-use crate::Input;
+use crate::compiler::{CompileError, Compiler, Instr, Operand};
+use crate::{Input, WireRef};
 
 /* ---------- */
 
@@ -22,6 +23,19 @@ impl Input for And {
         left.zip(right).map(|(left, right)| left & right)
     }
 
+    fn wires(&self) -> Vec<WireRef> {
+        let mut wires = self.left.wires();
+        wires.extend(self.right.wires());
+        wires
+    }
+
+    fn lower(&self, compiler: &mut Compiler) -> Result<Operand, CompileError> {
+        let left = self.left.lower(compiler)?;
+        let right = self.right.lower(compiler)?;
+
+        Ok(compiler.emit(Instr::And(left, right)))
+    }
+
     #[inline]
     fn dbg_string(&self) -> String {
         format!(
@@ -1,4 +1,5 @@
-use crate::Input;
+use crate::compiler::{CompileError, Compiler, Instr, Operand};
+use crate::{Input, WireRef};
 
 /* ---------- */
 
@@ -19,6 +20,16 @@ impl Input for Not {
         self.input.output().map(|val| !val)
     }
 
+    #[inline]
+    fn wires(&self) -> Vec<WireRef> {
+        self.input.wires()
+    }
+
+    fn lower(&self, compiler: &mut Compiler) -> Result<Operand, CompileError> {
+        let input = self.input.lower(compiler)?;
+        Ok(compiler.emit(Instr::Not(input)))
+    }
+
     #[inline]
     fn dbg_string(&self) -> String {
         format!("NotGate({})", self.input.dbg_string())
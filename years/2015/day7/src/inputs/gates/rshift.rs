@@ -1,4 +1,5 @@
-use crate::Input;
+use crate::compiler::{CompileError, Compiler, Instr, Operand};
+use crate::{Input, WireRef};
 
 /* ---------- */
 
@@ -19,7 +20,22 @@ impl Input for RightShift {
         let left = self.left.output();
         let right = self.right.output();
 
-        left.zip(right).map(|(left, right)| left >> right)
+        // Shifting every bit out leaves 0, like the compiled circuit does.
+        left.zip(right)
+            .map(|(left, right)| left.checked_shr(right as u32).unwrap_or(0))
+    }
+
+    fn wires(&self) -> Vec<WireRef> {
+        let mut wires = self.left.wires();
+        wires.extend(self.right.wires());
+        wires
+    }
+
+    fn lower(&self, compiler: &mut Compiler) -> Result<Operand, CompileError> {
+        let left = self.left.lower(compiler)?;
+        let right = self.right.lower(compiler)?;
+
+        Ok(compiler.emit(Instr::RightShift(left, right)))
     }
 
     #[inline]
@@ -1,7 +1,20 @@
+use super::WireRef;
+use crate::compiler::{CompileError, Compiler, Operand};
+
+/* ---------- */
+
 pub trait Input {
     fn output(&self) -> Option<u16>;
     fn dbg_string(&self) -> String;
 
+    /// Emits the instructions computing this input, see [`Compiler`].
+    fn lower(&self, compiler: &mut Compiler) -> Result<Operand, CompileError>;
+
+    /// Returns the wires this input reads from, in left to right order.
+    fn wires(&self) -> Vec<WireRef> {
+        Vec::new()
+    }
+
     fn into_input(self) -> Box<dyn Input>
     where
         Self: Sized + 'static,
@@ -1,4 +1,5 @@
 use super::Input;
+use crate::compiler::{CompileError, Compiler, Operand};
 
 pub struct Signal(u16);
 
@@ -15,6 +16,11 @@ impl Input for Signal {
         Some(self.0)
     }
 
+    #[inline]
+    fn lower(&self, _: &mut Compiler) -> Result<Operand, CompileError> {
+        Ok(Operand::Imm(self.0))
+    }
+
     #[inline]
     fn dbg_string(&self) -> String {
         format!("Signal({})", self.0)
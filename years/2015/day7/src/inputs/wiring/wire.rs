@@ -1,34 +1,66 @@
-use std::fmt::{Debug, Formatter, Result};
+use std::fmt::{self, Debug, Formatter};
+use std::rc::Rc;
 
+use super::WireRef;
+use crate::compiler::{CompileError, Compiler, Operand};
 use crate::Input;
 
 pub struct Wire {
-    id: &'static str,
+    id: Rc<str>,
     input: Option<Box<dyn Input>>,
+    forced: Option<u16>,
     output: Option<u16>,
 }
 
 impl Wire {
     #[inline]
-    pub fn new(id: &'static str) -> Self {
+    pub fn new(id: Rc<str>) -> Self {
         Self {
             id,
             input: None,
+            forced: None,
             output: None,
         }
     }
 
     #[inline]
-    pub fn id(&self) -> &'static str {
-        self.id
+    pub fn plug(&mut self, input: Box<dyn Input>) {
+        self.input = Some(input)
     }
 
     #[inline]
-    pub fn plug(&mut self, input: Box<dyn Input>) {
-        self.input = Some(input)
+    pub fn unplug(&mut self) -> Option<Box<dyn Input>> {
+        self.input.take()
+    }
+
+    /// Overrides the wire's signal, regardless of what is plugged in.
+    #[inline]
+    pub fn force(&mut self, value: u16) {
+        self.forced = Some(value)
+    }
+
+    /// Removes the override set by [`Wire::force`], if any.
+    #[inline]
+    pub fn release(&mut self) {
+        self.forced = None
+    }
+
+    #[inline]
+    pub fn is_forced(&self) -> bool {
+        self.forced.is_some()
+    }
+
+    /// Whether the signal is known without having to evaluate the input.
+    #[inline]
+    pub fn is_settled(&self) -> bool {
+        self.forced.is_some() || self.output.is_some()
     }
 
     pub fn output(&mut self) -> Option<u16> {
+        if self.forced.is_some() {
+            return self.forced;
+        }
+
         if self.output.is_none() {
             if let Some(input) = &self.input {
                 self.output = input.output()
@@ -42,19 +74,42 @@ impl Wire {
     pub fn reset(&mut self) {
         self.output = None;
     }
-}
 
-impl Debug for Wire {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let input_str = match &self.input {
+    /// Lowers whatever drives the wire, a forced signal being lowered to a constant.
+    pub fn lower_driver(&self, compiler: &mut Compiler) -> Result<Operand, CompileError> {
+        match (self.forced, &self.input) {
+            (Some(value), _) => Ok(Operand::Imm(value)),
+            (None, Some(input)) => input.lower(compiler),
+            (None, None) => Err(CompileError::Undriven(self.id.to_string())),
+        }
+    }
+
+    #[inline]
+    pub fn input_string(&self) -> String {
+        match &self.input {
             Some(input) => input.dbg_string(),
             None => String::from("None"),
-        };
+        }
+    }
+
+    #[inline]
+    pub fn sources(&self) -> Vec<WireRef> {
+        match &self.input {
+            Some(input) => input.wires(),
+            None => Vec::new(),
+        }
+    }
+}
 
+impl Debug for Wire {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Wire {{ id: {}, input: {:?}, output: {:?}}}",
-            self.id, input_str, self.output
+            "Wire {{ id: {}, input: {:?}, forced: {:?}, output: {:?}}}",
+            self.id,
+            self.input_string(),
+            self.forced,
+            self.output
         )
     }
 }
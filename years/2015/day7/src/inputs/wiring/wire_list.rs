@@ -1,9 +1,28 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
 use super::WireRef;
+use crate::Input;
+
+/* ---------- */
+
+/// A wire whose signal differs from the one recorded by [`Wires::save_baseline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WireChange<'a> {
+    pub id: &'a str,
+    pub baseline: Option<u16>,
+    pub current: Option<u16>,
+}
 
 /* ---------- */
 
+/// The wires of a circuit, in the order they were first referenced.
 #[derive(Default, Debug)]
-pub struct Wires(Vec<WireRef>);
+pub struct Wires {
+    ids: HashMap<Rc<str>, usize>,
+    list: Vec<WireRef>,
+    baseline: Vec<Option<u16>>,
+}
 
 impl Wires {
     #[inline]
@@ -11,33 +30,144 @@ impl Wires {
         Self::default()
     }
 
-    pub fn entry(&mut self, id: &'static str) -> WireRef {
-        self.0
-            .iter()
-            .find(|wire| wire.id() == id)
-            .cloned()
-            .unwrap_or_else(|| {
+    pub fn entry(&mut self, id: &str) -> WireRef {
+        match self.wire(id) {
+            Some(wire) => wire.clone(),
+            None => {
                 let new = WireRef::new(id);
 
                 self.push(new.clone());
                 new
-            })
+            }
+        }
     }
 
     #[inline]
     pub fn push(&mut self, new: WireRef) {
-        if !self.0.iter().any(|wire| wire.id() == new.id()) {
-            self.0.push(new)
+        if !self.ids.contains_key(new.id()) {
+            self.ids.insert(Rc::from(new.id()), self.list.len());
+            self.list.push(new)
         }
     }
 
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &WireRef> {
+        self.list.iter()
+    }
+
     #[inline]
     pub fn reset(&self) {
-        self.0.iter().for_each(|wire| wire.reset())
+        self.list.iter().for_each(|wire| wire.reset())
     }
 
     #[inline]
-    pub fn wire(&self, id: &'static str) -> Option<&WireRef> {
-        self.0.iter().find(|wire| wire.id() == id)
+    pub fn wire(&self, id: &str) -> Option<&WireRef> {
+        self.ids.get(id).map(|&idx| &self.list[idx])
+    }
+
+    /// Computes the signal of the wire `id`.
+    ///
+    /// The wires it depends on are settled bottom-up first, so that long chains of wires don't
+    /// recurse through the whole circuit.
+    pub fn query(&self, id: &str) -> Option<u16> {
+        let wire = self.wire(id)?;
+        let mut expanded = HashSet::new();
+        let mut stack = vec![wire.clone()];
+
+        while let Some(current) = stack.last() {
+            if current.is_settled() {
+                stack.pop();
+            } else if expanded.insert(Rc::<str>::from(current.id())) {
+                let sources = current.sources();
+                stack.extend(sources.into_iter().filter(|src| !src.is_settled()));
+            } else {
+                let current = stack.pop().expect("a non empty stack");
+                current.output();
+            }
+        }
+
+        wire.output()
+    }
+
+    /// Forces the signal of the wire `id` to `value`.
+    ///
+    /// Returns `false` if no such wire exists.
+    pub fn set(&self, id: &str, value: u16) -> bool {
+        self.override_wires([(id, value)])
+    }
+
+    /// Forces the signal of every given wire at once.
+    ///
+    /// Returns `false`, and leaves the circuit untouched, if one of the wires doesn't exist.
+    pub fn override_wires<'a>(&self, overrides: impl IntoIterator<Item = (&'a str, u16)>) -> bool {
+        let overrides = overrides
+            .into_iter()
+            .map(|(id, value)| self.wire(id).map(|wire| (wire, value)))
+            .collect::<Option<Vec<_>>>();
+
+        match overrides {
+            Some(overrides) => {
+                overrides
+                    .into_iter()
+                    .for_each(|(wire, value)| wire.force(value));
+                self.reset();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes every override, going back to the circuit as it was assembled.
+    pub fn clear_overrides(&self) {
+        self.list.iter().for_each(|wire| wire.release());
+        self.reset()
+    }
+
+    /// Records the current signal of every wire, to be compared against by [`Wires::changed`].
+    pub fn save_baseline(&mut self) {
+        self.baseline = self.list.iter().map(|wire| self.query(wire.id())).collect();
+    }
+
+    /// Lists the wires whose signal differs from the saved baseline.
+    pub fn changed(&self) -> Vec<WireChange<'_>> {
+        self.list
+            .iter()
+            .zip(&self.baseline)
+            .filter_map(|(wire, &baseline)| {
+                let current = self.query(wire.id());
+
+                (current != baseline).then_some(WireChange {
+                    id: wire.id(),
+                    baseline,
+                    current,
+                })
+            })
+            .collect()
+    }
+
+    /// Lists the wire `id` followed by every wire it depends on, each one only once.
+    pub fn trace(&self, id: &str) -> Vec<WireRef> {
+        let mut seen = HashSet::new();
+        let mut stack = self.wire(id).cloned().into_iter().collect::<Vec<_>>();
+        let mut ret = Vec::new();
+
+        while let Some(wire) = stack.pop() {
+            if !seen.insert(Rc::<str>::from(wire.id())) {
+                continue;
+            }
+
+            stack.extend(wire.sources().into_iter().rev());
+            ret.push(wire);
+        }
+
+        ret
+    }
+}
+
+impl Drop for Wires {
+    fn drop(&mut self) {
+        // Unplugging every wire first keeps the drop of a long chain of wires from recursing down
+        // the whole chain.
+        self.list.iter().for_each(|wire| drop(wire.unplug()))
     }
 }
@@ -1,52 +1,113 @@
 use std::cell::RefCell;
-use std::fmt::{Debug, Formatter, Result};
+use std::fmt::{self, Debug, Formatter};
 use std::rc::Rc;
 
 use super::Wire;
+use crate::compiler::{CompileError, Compiler, Operand};
 use crate::Input;
 
 /* ---------- */
 
-pub struct WireRef(Rc<RefCell<Wire>>);
+pub struct WireRef {
+    id: Rc<str>,
+    inner: Rc<RefCell<Wire>>,
+}
 
 impl WireRef {
     #[inline]
-    pub fn new(id: &'static str) -> Self {
-        Self(Rc::new(RefCell::new(Wire::new(id))))
+    pub fn new(id: &str) -> Self {
+        let id = Rc::<str>::from(id);
+
+        Self {
+            inner: Rc::new(RefCell::new(Wire::new(Rc::clone(&id)))),
+            id,
+        }
     }
 
     #[inline]
-    pub fn id(&self) -> &'static str {
-        (*self.0).borrow().id()
+    pub fn id(&self) -> &str {
+        &self.id
     }
 
     #[inline]
     pub fn plug(&self, input: Box<dyn Input>) {
-        (*self.0).borrow_mut().plug(input)
+        (*self.inner).borrow_mut().plug(input)
+    }
+
+    #[inline]
+    pub fn unplug(&self) -> Option<Box<dyn Input>> {
+        (*self.inner).borrow_mut().unplug()
     }
 
     #[inline]
     pub fn reset(&self) {
-        (*self.0).borrow_mut().reset()
+        (*self.inner).borrow_mut().reset()
+    }
+
+    #[inline]
+    pub fn force(&self, value: u16) {
+        (*self.inner).borrow_mut().force(value)
+    }
+
+    #[inline]
+    pub fn release(&self) {
+        (*self.inner).borrow_mut().release()
+    }
+
+    #[inline]
+    pub fn is_forced(&self) -> bool {
+        (*self.inner).borrow().is_forced()
+    }
+
+    #[inline]
+    pub fn is_settled(&self) -> bool {
+        (*self.inner).borrow().is_settled()
+    }
+
+    #[inline]
+    pub fn lower_driver(&self, compiler: &mut Compiler) -> Result<Operand, CompileError> {
+        (*self.inner).borrow().lower_driver(compiler)
+    }
+
+    #[inline]
+    pub fn input_string(&self) -> String {
+        (*self.inner).borrow().input_string()
+    }
+
+    #[inline]
+    pub fn sources(&self) -> Vec<WireRef> {
+        (*self.inner).borrow().sources()
     }
 }
 
 impl Debug for WireRef {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let inner = (*self.0).borrow();
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let inner = (*self.inner).borrow();
         write!(f, "{inner:?}")
     }
 }
 
 impl Clone for WireRef {
     fn clone(&self) -> Self {
-        Self(Rc::clone(&self.0))
+        Self {
+            id: Rc::clone(&self.id),
+            inner: Rc::clone(&self.inner),
+        }
     }
 }
 
 impl Input for WireRef {
+    /// A wire already being evaluated further up is part of a loop and has no signal.
     fn output(&self) -> Option<u16> {
-        (*self.0).borrow_mut().output()
+        (*self.inner).try_borrow_mut().ok()?.output()
+    }
+
+    fn wires(&self) -> Vec<WireRef> {
+        vec![self.clone()]
+    }
+
+    fn lower(&self, compiler: &mut Compiler) -> Result<Operand, CompileError> {
+        compiler.wire(self.id())
     }
 
     fn dbg_string(&self) -> String {
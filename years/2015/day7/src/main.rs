@@ -1,5 +1,11 @@
+mod compiler;
 mod inputs;
+mod parser;
+mod repl;
+
+use compiler::*;
 use inputs::*;
+use parser::{Expr, Gate, ParseError, Statement, Term};
 
 /* ---------- */
 
@@ -8,107 +14,380 @@ const INPUT: &str = utils::input_str!();
 /* ---------- */
 
 #[inline]
-fn is_ascii_number(s: &'static str) -> bool {
-    s.chars().all(|c| c.is_ascii_digit())
+fn input_from_term(term: Term, wires: &mut Wires) -> Box<dyn Input> {
+    match term {
+        Term::Signal(value) => Signal::new(value).into_input(),
+        Term::Wire(id) => wires.entry(id).into_input(),
+    }
 }
 
 /* ---------- */
 
-#[inline]
-fn input_from_str(input: &'static str, wires: &mut Wires) -> Box<dyn Input> {
-    if is_ascii_number(input) {
-        let value = input.parse::<u16>().expect("a valid u16 for signal value");
-        Signal::new(value).into_input()
-    } else {
-        wires.entry(input).into_input()
-    }
+fn assemble_circuit(statement: &Statement, wires: &mut Wires) {
+    let output = wires.entry(statement.output);
+
+    let input = match statement.expr {
+        Expr::Term(term) => input_from_term(term, wires),
+        Expr::Not(term) => Not::new(input_from_term(term, wires)).into_input(),
+        Expr::Gate(gate, left, right) => {
+            let left = input_from_term(left, wires);
+            let right = input_from_term(right, wires);
+
+            match gate {
+                Gate::And => And::new(left, right).into_input(),
+                Gate::Or => Or::new(left, right).into_input(),
+                Gate::LeftShift => LeftShift::new(left, right).into_input(),
+                Gate::RightShift => RightShift::new(left, right).into_input(),
+            }
+        }
+    };
+
+    output.plug(input);
 }
 
 /* ---------- */
 
-fn assemble_circuit(line: &'static str, wires: &mut Wires) {
-    let parts = line.split_ascii_whitespace().collect::<Vec<_>>();
-    let output = match parts.last() {
-        Some(part) => *part,
-        None => panic!("invalid line {line}"),
-    };
+fn load_circuit(input: &str) -> Result<Wires, Vec<ParseError>> {
+    let mut wires = Wires::new();
 
-    let output = wires.entry(output);
+    parser::parse(input)?
+        .iter()
+        .for_each(|statement| assemble_circuit(statement, &mut wires));
+    Ok(wires)
+}
 
-    match parts.len() {
-        3 => {
-            let input = input_from_str(parts[0], wires);
-            output.plug(input);
-        }
-        4 => {
-            let input = input_from_str(parts[1], wires);
-            let not_gate = Not::new(input);
+/* ---------- */
 
-            output.plug(not_gate.into_input());
-        }
-        5 if parts[1] == "AND" => {
-            let left = input_from_str(parts[0], wires);
-            let right = input_from_str(parts[2], wires);
-            let and_gate = And::new(left, right);
+fn part1(wires: &mut Wires) -> u16 {
+    wires.save_baseline();
 
-            output.plug(and_gate.into_input());
-        }
-        5 if parts[1] == "OR" => {
-            let left = input_from_str(parts[0], wires);
-            let right = input_from_str(parts[2], wires);
-            let or_gate = Or::new(left, right);
+    wires.query("a").expect("a vaild output for 'a' wire")
+}
+
+/* ---------- */
+
+fn part2(wires: &Wires, new_signal: u16) -> u16 {
+    assert!(wires.set("b", new_signal), "a existing wire id 'b'");
+
+    wires.query("a").expect("a vaild output for 'a' wire")
+}
+
+/* ---------- */
+
+fn bench(wires: &Wires, program: &Program) {
+    const RUNS: usize = 1_000;
+
+    println!("[BENCH] {RUNS} evaluations of 'a' walking the wires");
+    utils::bench!((0..RUNS).for_each(|_| {
+        wires.reset();
+        std::hint::black_box(wires.query("a"));
+    }));
+
+    assert_eq!(program.eval("a"), wires.query("a"));
+
+    let reg = program.register("a").expect("a existing wire id 'a'");
+    let mut regs = vec![0; program.instrs().len()];
+
+    println!("[BENCH] {RUNS} evaluations of 'a' running the compiled program");
+    utils::bench!((0..RUNS).for_each(|_| {
+        program.run_into(&mut regs);
+        std::hint::black_box(regs[reg]);
+    }));
+}
+
+/* ---------- */
 
-            output.plug(or_gate.into_input());
+enum Mode {
+    Answers,
+    Repl,
+    Bench,
+    Codegen,
+}
+
+/* ---------- */
+
+fn main() {
+    let mut mode = Mode::Answers;
+    let mut path = None;
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "repl" => mode = Mode::Repl,
+            "bench" => mode = Mode::Bench,
+            "codegen" => mode = Mode::Codegen,
+            _ => path = Some(arg),
         }
-        5 if parts[1] == "LSHIFT" => {
-            let left = input_from_str(parts[0], wires);
-            let right = input_from_str(parts[2], wires);
-            let lshift_gate = LeftShift::new(left, right);
+    }
 
-            output.plug(lshift_gate.into_input());
+    let input = match path {
+        Some(path) => std::fs::read_to_string(&path).expect("a readable circuit file"),
+        None => String::from(INPUT),
+    };
+    let mut wires = match load_circuit(&input) {
+        Ok(wires) => wires,
+        Err(errors) => {
+            errors.iter().for_each(|err| eprintln!("{err}"));
+            std::process::exit(1);
         }
-        5 if parts[1] == "RSHIFT" => {
-            let left = input_from_str(parts[0], wires);
-            let right = input_from_str(parts[2], wires);
-            let rshift_gate = RightShift::new(left, right);
+    };
+
+    // Every mode but the answers works on arbitrary circuits, which may lack the puzzle wires.
+    if !matches!(mode, Mode::Repl) && wires.wire("a").is_none() {
+        eprintln!("the circuit has no wire 'a'");
+        std::process::exit(1);
+    }
 
-            output.plug(rshift_gate.into_input());
+    match mode {
+        Mode::Answers => {
+            let ret = part1(&mut wires);
+            println!("[PART 1] Answer = {}", ret);
+            println!("[PART 2] Answer = {}", part2(&wires, ret));
         }
-        _ => {
-            panic!("Parse error => {line}")
+        Mode::Repl => {
+            wires.save_baseline();
+            repl::run(&wires).expect("a working stdin/stdout")
         }
-    }
+        Mode::Bench => {
+            let program = Compiler::compile(&wires).expect("a compilable circuit");
+            bench(&wires, &program)
+        }
+        Mode::Codegen => {
+            let program = Compiler::compile(&wires).expect("a compilable circuit");
+            let code = program
+                .to_rust("circuit", "a")
+                .expect("a existing wire id 'a'");
 
-    wires.push(output)
+            print!("{code}");
+        }
+    }
 }
 
 /* ---------- */
 
-fn part1(wires: &mut Wires) -> u16 {
-    INPUT.lines().for_each(|line| assemble_circuit(line, wires));
+#[cfg(test)]
+mod test {
+    use crate::parser::{parse, ErrorKind, ParseError};
+    use crate::{load_circuit, CompileError, Compiler, Input, Not, Solver, Wires};
 
-    let wire = wires.wire("a").expect("a existing wire id 'a'");
-    wire.output().expect("a vaild output for 'a' wire")
-}
+    const EXAMPLE: &str = "123 -> x
+456 -> y
+x AND y -> d
+x OR y -> e
+x LSHIFT 2 -> f
+y RSHIFT 2 -> g
+NOT x -> h
+NOT y -> i";
 
-/* ---------- */
+    fn example() -> Wires {
+        let mut wires = load_circuit(EXAMPLE).unwrap();
+        wires.save_baseline();
+        wires
+    }
 
-fn part2(wires: &mut Wires, new_signal: u16) -> u16 {
-    wires.reset();
+    #[test]
+    fn test_example() {
+        let wires = example();
 
-    let b = wires.wire("b").expect("a existing wire id 'b'");
-    b.plug(Signal::new(new_signal).into_input());
+        assert_eq!(wires.query("d"), Some(72));
+        assert_eq!(wires.query("e"), Some(507));
+        assert_eq!(wires.query("f"), Some(492));
+        assert_eq!(wires.query("g"), Some(114));
+        assert_eq!(wires.query("h"), Some(65412));
+        assert_eq!(wires.query("i"), Some(65079));
+        assert_eq!(wires.query("x"), Some(123));
+        assert_eq!(wires.query("y"), Some(456));
+    }
 
-    let wire = wires.wire("a").expect("a existing wire id 'a'");
-    wire.output().expect("a vaild output for 'a' wire")
-}
+    #[test]
+    fn test_overrides() {
+        let wires = example();
 
-/* ---------- */
+        assert!(wires.override_wires([("x", 0), ("y", 0xffff)]));
+        assert_eq!(wires.query("d"), Some(0));
+        assert_eq!(wires.query("e"), Some(0xffff));
 
-fn main() {
-    let mut wires = Wires::new();
+        let changed = wires
+            .changed()
+            .into_iter()
+            .map(|c| c.id)
+            .collect::<Vec<_>>();
+        assert_eq!(changed, ["x", "y", "d", "e", "f", "g", "h", "i"]);
+
+        assert!(!wires.set("z", 1));
+        wires.clear_overrides();
+        assert_eq!(wires.query("d"), Some(72));
+        assert!(wires.changed().is_empty());
+    }
+
+    #[test]
+    fn test_trace() {
+        let wires = example();
+        let trace = wires.trace("d");
+        let ids = trace.iter().map(|w| w.id()).collect::<Vec<_>>();
+
+        assert_eq!(ids, ["d", "x", "y"]);
+    }
+
+    #[test]
+    fn test_long_chain() {
+        let mut input = String::from("1 -> w0\n");
+        (1..50_000).for_each(|i| input.push_str(&format!("w{} OR {i} -> w{i}\n", i - 1)));
+
+        let wires = load_circuit(&input).unwrap();
+        assert_eq!(wires.query("w49999"), Some(u16::MAX));
+    }
+
+    #[test]
+    fn test_compiled() {
+        let wires = example();
+        let program = Compiler::compile(&wires).unwrap();
 
-    let ret = part1(&mut wires);
-    println!("[PART 1] Answer = {}", ret);
-    println!("[PART 2] Answer = {}", part2(&mut wires, ret));
+        for id in ["d", "e", "f", "g", "h", "i", "x", "y"] {
+            assert_eq!(program.eval(id), wires.query(id));
+        }
+
+        wires.set("x", 0);
+        let program = Compiler::compile(&wires).unwrap();
+        assert_eq!(program.eval("d"), Some(0));
+        assert_eq!(program.eval("e"), Some(456));
+
+        let code = program.to_rust("circuit", "d").unwrap();
+        assert_eq!(code.lines().count(), 6);
+        assert!(code.contains("= 0; // x"));
+        assert!(!code.contains("// e"));
+    }
+
+    #[test]
+    fn test_wide_shifts() {
+        let wires = load_circuit("1 -> x\n20 -> s\nx LSHIFT s -> y\nx RSHIFT 16 -> z").unwrap();
+        let program = Compiler::compile(&wires).unwrap();
+
+        for id in ["y", "z"] {
+            assert_eq!(wires.query(id), Some(0));
+            assert_eq!(program.eval(id), Some(0));
+        }
+
+        let code = program.to_rust("circuit", "y").unwrap();
+        assert!(code.contains("checked_shl"));
+        assert!(!code.contains("<<"));
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let mut wires = Wires::new();
+        let x = wires.entry("x");
+        wires.entry("y").plug(x.into_input());
+        assert_eq!(
+            Compiler::compile(&wires).unwrap_err(),
+            CompileError::Undriven(String::from("x"))
+        );
+
+        // The parser rejects loops, only a circuit assembled by hand can still have one.
+        let mut wires = Wires::new();
+        let (x, y) = (wires.entry("x"), wires.entry("y"));
+        y.plug(x.clone().into_input());
+        x.plug(Not::new(y.into_input()).into_input());
+        assert!(matches!(
+            Compiler::compile(&wires).unwrap_err(),
+            CompileError::Cycle(_)
+        ));
+        assert_eq!(wires.query("x"), None);
+        assert_eq!(wires.query("y"), None);
+    }
+
+    #[test]
+    fn test_solver() {
+        let program = Compiler::compile(&example()).unwrap();
+
+        let solver = Solver::new(&program, "d", &["x"]).unwrap();
+        let solutions = solver.solve(72).collect::<Vec<_>>();
+        assert_eq!(solutions.len(), 4096);
+        assert!(solutions.contains(&vec![123]));
+        assert!(solutions.iter().all(|sol| sol[0] & 456 == 72));
+        assert_eq!(solver.solve(1).next(), None);
+
+        let solver = Solver::new(&program, "f", &["x"]).unwrap();
+        assert_eq!(
+            solver.solve(492).collect::<Vec<_>>(),
+            [[123], [16507], [32891], [49275]]
+        );
+
+        // Solutions come one at a time, without going through the whole space first.
+        let solver = Solver::new(&program, "d", &["x", "y"]).unwrap();
+        assert_eq!(
+            solver.solve(0).take(3).collect::<Vec<_>>(),
+            [[0, 0], [0, 1], [0, 2]]
+        );
+
+        assert!(Solver::new(&program, "d", &["z"]).is_none());
+    }
+
+    #[test]
+    fn test_parser() {
+        let circuit = "# comment\n\n123 -> x # trailing comment\nNOT x -> y\n";
+        assert_eq!(parse(circuit).unwrap().len(), 2);
+
+        let errors = |circuit| {
+            parse(circuit)
+                .unwrap_err()
+                .into_iter()
+                .map(|ParseError { line, kind }| (line, kind))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            errors("1 -> x\nFOO x -> y\nx ANDD x -> z"),
+            [
+                (2, ErrorKind::UnknownKeyword(String::from("FOO"))),
+                (3, ErrorKind::UnknownKeyword(String::from("ANDD")))
+            ]
+        );
+        assert_eq!(
+            errors("1 x\n70000 -> y\n1 -> z w\nNOT -> a"),
+            [
+                (
+                    1,
+                    ErrorKind::Unexpected {
+                        found: String::from("wire 'x'"),
+                        expected: "a gate or '->'"
+                    }
+                ),
+                (2, ErrorKind::InvalidSignal(String::from("70000"))),
+                (
+                    3,
+                    ErrorKind::Unexpected {
+                        found: String::from("wire 'w'"),
+                        expected: "end of line"
+                    }
+                ),
+                (
+                    4,
+                    ErrorKind::Unexpected {
+                        found: String::from("'->'"),
+                        expected: "a signal or a wire"
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            errors("1 -> x\nx AND y -> z\n2 -> x\nNOT y -> w"),
+            [
+                (2, ErrorKind::NeverDriven(String::from("y"))),
+                (
+                    3,
+                    ErrorKind::DrivenTwice {
+                        wire: String::from("x"),
+                        first: 1
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            errors("x -> y\nNOT y -> x\n1 -> a\na AND b -> b\nb -> c"),
+            [
+                (1, ErrorKind::Cycle(String::from("y"))),
+                (4, ErrorKind::Cycle(String::from("b"))),
+            ]
+        );
+    }
 }
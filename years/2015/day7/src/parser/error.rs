@@ -0,0 +1,58 @@
+use std::fmt::{Display, Formatter, Result};
+
+/* ---------- */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    InvalidToken(String),
+    InvalidSignal(String),
+    UnknownKeyword(String),
+    Unexpected {
+        found: String,
+        expected: &'static str,
+    },
+    DrivenTwice {
+        wire: String,
+        first: usize,
+    },
+    NeverDriven(String),
+    Cycle(String),
+}
+
+/* ---------- */
+
+/// A diagnostic tied to the (1-based) line of the circuit it comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+impl ParseError {
+    #[inline]
+    pub fn new(line: usize, kind: ErrorKind) -> Self {
+        Self { line, kind }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.kind {
+            ErrorKind::InvalidToken(token) => write!(f, "invalid token '{token}'"),
+            ErrorKind::InvalidSignal(signal) => {
+                write!(f, "'{signal}' doesn't fit in a 16-bit signal")
+            }
+            ErrorKind::UnknownKeyword(keyword) => write!(f, "unknown keyword '{keyword}'"),
+            ErrorKind::Unexpected { found, expected } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            ErrorKind::DrivenTwice { wire, first } => {
+                write!(f, "wire '{wire}' is already driven at line {first}")
+            }
+            ErrorKind::NeverDriven(wire) => write!(f, "wire '{wire}' is never driven"),
+            ErrorKind::Cycle(wire) => write!(f, "wire '{wire}' depends on its own signal"),
+        }
+    }
+}
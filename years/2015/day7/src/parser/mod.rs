@@ -0,0 +1,7 @@
+mod error;
+mod parse;
+mod token;
+
+pub use error::*;
+pub use parse::*;
+pub use token::*;
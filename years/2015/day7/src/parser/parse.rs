@@ -0,0 +1,276 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{tokenize, ErrorKind, Keyword, ParseError, Token};
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Term<'a> {
+    Signal(u16),
+    Wire(&'a str),
+}
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gate {
+    And,
+    Or,
+    LeftShift,
+    RightShift,
+}
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expr<'a> {
+    Term(Term<'a>),
+    Not(Term<'a>),
+    Gate(Gate, Term<'a>, Term<'a>),
+}
+
+impl<'a> Expr<'a> {
+    pub fn terms(&self) -> Vec<Term<'a>> {
+        match *self {
+            Self::Term(term) | Self::Not(term) => vec![term],
+            Self::Gate(_, left, right) => vec![left, right],
+        }
+    }
+}
+
+/* ---------- */
+
+/// A single `<expr> -> <wire>` line of a circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statement<'a> {
+    pub line: usize,
+    pub expr: Expr<'a>,
+    pub output: &'a str,
+}
+
+/* ---------- */
+
+struct Cursor<'t, 'a> {
+    tokens: &'t [Token<'a>],
+    line: usize,
+}
+
+impl<'a> Cursor<'_, 'a> {
+    #[inline]
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.first().copied()
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<Token<'a>, ParseError> {
+        match self.tokens.split_first() {
+            Some((&token, rest)) => {
+                self.tokens = rest;
+                Ok(token)
+            }
+            None => Err(self.unexpected(String::from("end of line"), expected)),
+        }
+    }
+
+    #[inline]
+    fn unexpected(&self, found: String, expected: &'static str) -> ParseError {
+        ParseError::new(self.line, ErrorKind::Unexpected { found, expected })
+    }
+
+    fn term(&mut self) -> Result<Term<'a>, ParseError> {
+        const EXPECTED: &str = "a signal or a wire";
+
+        match self.next(EXPECTED)? {
+            Token::Signal(signal) => Ok(Term::Signal(signal)),
+            Token::Wire(wire) => Ok(Term::Wire(wire)),
+            token => Err(self.unexpected(token.to_string(), EXPECTED)),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr<'a>, ParseError> {
+        if let Some(Token::Keyword(Keyword::Not)) = self.peek() {
+            self.next("NOT")?;
+            return Ok(Expr::Not(self.term()?));
+        }
+
+        let left = self.term()?;
+
+        let gate = match self.peek() {
+            Some(Token::Keyword(Keyword::And)) => Gate::And,
+            Some(Token::Keyword(Keyword::Or)) => Gate::Or,
+            Some(Token::Keyword(Keyword::LeftShift)) => Gate::LeftShift,
+            Some(Token::Keyword(Keyword::RightShift)) => Gate::RightShift,
+            _ => return Ok(Expr::Term(left)),
+        };
+
+        self.next("a gate")?;
+        Ok(Expr::Gate(gate, left, self.term()?))
+    }
+
+    fn statement(&mut self) -> Result<Statement<'a>, ParseError> {
+        let expr = self.expr()?;
+
+        match self.next("'->'")? {
+            Token::Arrow => (),
+            token => return Err(self.unexpected(token.to_string(), "a gate or '->'")),
+        }
+
+        let output = match self.next("an output wire")? {
+            Token::Wire(wire) => wire,
+            token => return Err(self.unexpected(token.to_string(), "an output wire")),
+        };
+
+        match self.peek() {
+            None => Ok(Statement {
+                line: self.line,
+                expr,
+                output,
+            }),
+            Some(token) => Err(self.unexpected(token.to_string(), "end of line")),
+        }
+    }
+}
+
+/* ---------- */
+
+/// Reports every wire closing a loop in the circuit, at the line driving it. The walk is iterative
+/// so that long chains of wires don't overflow the stack.
+fn find_cycles(statements: &[Statement<'_>]) -> Vec<ParseError> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum State {
+        Open,
+        Done,
+    }
+
+    let mut drivers = HashMap::new();
+    for statement in statements {
+        drivers.entry(statement.output).or_insert(statement);
+    }
+
+    let sources = |wire: &str| {
+        drivers
+            .get(wire)
+            .map(|statement| statement.expr.terms())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|term| match term {
+                Term::Wire(wire) if drivers.contains_key(wire) => Some(wire),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut states = HashMap::new();
+    let mut errors = Vec::new();
+
+    for statement in statements {
+        if states.contains_key(statement.output) {
+            continue;
+        }
+
+        states.insert(statement.output, State::Open);
+        let mut stack = vec![(statement.output, sources(statement.output))];
+
+        while let Some((wire, pending)) = stack.last_mut() {
+            let Some(src) = pending.pop() else {
+                states.insert(*wire, State::Done);
+                stack.pop();
+                continue;
+            };
+
+            match states.get(src) {
+                Some(State::Done) => (),
+                Some(State::Open) => {
+                    let kind = ErrorKind::Cycle(src.to_string());
+                    errors.push(ParseError::new(drivers[src].line, kind));
+                }
+                None => {
+                    states.insert(src, State::Open);
+                    stack.push((src, sources(src)));
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+/* ---------- */
+
+/// Parses a whole circuit, one statement per line. Blank lines and comments, starting with
+/// [`super::COMMENT`], are skipped.
+///
+/// Circuits where a wire feeds back into itself are rejected, as their signals can't settle.
+///
+/// On failure, every error found is returned, sorted by line.
+pub fn parse(input: &str) -> Result<Vec<Statement<'_>>, Vec<ParseError>> {
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+
+    for (idx, line) in input.lines().enumerate() {
+        let line_nb = idx + 1;
+
+        let tokens = match tokenize(line, line_nb) {
+            Ok(tokens) if tokens.is_empty() => continue,
+            Ok(tokens) => tokens,
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        };
+
+        let mut cursor = Cursor {
+            tokens: &tokens,
+            line: line_nb,
+        };
+
+        match cursor.statement() {
+            Ok(statement) => statements.push(statement),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    let syntax_ok = errors.is_empty();
+    let mut drivers = HashMap::new();
+
+    for statement in &statements {
+        if let Some(&first) = drivers.get(statement.output) {
+            let kind = ErrorKind::DrivenTwice {
+                wire: statement.output.to_string(),
+                first,
+            };
+
+            errors.push(ParseError::new(statement.line, kind));
+        } else {
+            drivers.insert(statement.output, statement.line);
+        }
+    }
+
+    // A line that failed to parse may well be the one driving the wire, so only look for wires
+    // that are never driven once the syntax is right. Each one is reported where it's first read.
+    if syntax_ok {
+        let mut reported = HashSet::new();
+
+        for statement in &statements {
+            for term in statement.expr.terms() {
+                if let Term::Wire(wire) = term {
+                    if !drivers.contains_key(wire) && reported.insert(wire) {
+                        let kind = ErrorKind::NeverDriven(wire.to_string());
+                        errors.push(ParseError::new(statement.line, kind));
+                    }
+                }
+            }
+        }
+    }
+
+    if syntax_ok {
+        errors.extend(find_cycles(&statements));
+    }
+
+    match errors.is_empty() {
+        true => Ok(statements),
+        false => {
+            errors.sort_by_key(|err| err.line);
+            Err(errors)
+        }
+    }
+}
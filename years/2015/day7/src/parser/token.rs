@@ -0,0 +1,106 @@
+use std::fmt::{Display, Formatter, Result};
+
+use super::{ErrorKind, ParseError};
+
+/* ---------- */
+
+/// Everything after this character, up to the end of the line, is ignored.
+pub const COMMENT: char = '#';
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    And,
+    Or,
+    LeftShift,
+    RightShift,
+    Not,
+}
+
+impl Keyword {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "AND" => Some(Self::And),
+            "OR" => Some(Self::Or),
+            "LSHIFT" => Some(Self::LeftShift),
+            "RSHIFT" => Some(Self::RightShift),
+            "NOT" => Some(Self::Not),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Keyword {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let s = match self {
+            Self::And => "AND",
+            Self::Or => "OR",
+            Self::LeftShift => "LSHIFT",
+            Self::RightShift => "RSHIFT",
+            Self::Not => "NOT",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/* ---------- */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token<'a> {
+    Signal(u16),
+    Wire(&'a str),
+    Keyword(Keyword),
+    Arrow,
+}
+
+impl<'a> Token<'a> {
+    fn from_str(s: &'a str, line: usize) -> std::result::Result<Self, ParseError> {
+        let first = s.chars().next().unwrap_or_default();
+
+        if s == "->" {
+            Ok(Self::Arrow)
+        } else if s.chars().all(|c| c.is_ascii_digit()) {
+            s.parse::<u16>()
+                .map(Self::Signal)
+                .map_err(|_| ParseError::new(line, ErrorKind::InvalidSignal(s.to_string())))
+        } else if s.chars().all(|c| c.is_ascii_uppercase()) {
+            Keyword::from_str(s)
+                .map(Self::Keyword)
+                .ok_or_else(|| ParseError::new(line, ErrorKind::UnknownKeyword(s.to_string())))
+        } else if first.is_ascii_alphabetic() && s.chars().all(|c| c.is_ascii_alphanumeric()) {
+            Ok(Self::Wire(s))
+        } else {
+            Err(ParseError::new(
+                line,
+                ErrorKind::InvalidToken(s.to_string()),
+            ))
+        }
+    }
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Signal(signal) => write!(f, "signal {signal}"),
+            Self::Wire(wire) => write!(f, "wire '{wire}'"),
+            Self::Keyword(keyword) => write!(f, "keyword '{keyword}'"),
+            Self::Arrow => write!(f, "'->'"),
+        }
+    }
+}
+
+/* ---------- */
+
+/// Splits a single line into tokens, comments excluded.
+pub fn tokenize(line: &str, line_nb: usize) -> std::result::Result<Vec<Token<'_>>, ParseError> {
+    let code = match line.find(COMMENT) {
+        Some(idx) => &line[..idx],
+        None => line,
+    };
+
+    code.split_ascii_whitespace()
+        .map(|s| Token::from_str(s, line_nb))
+        .collect()
+}
@@ -0,0 +1,199 @@
+use std::io::{self, BufRead, Write};
+
+use crate::compiler::{Compiler, Solver};
+use crate::inputs::Wires;
+
+/* ---------- */
+
+const HELP: &str = "\
+commands:
+  set <wire> <value>   override the signal of a wire
+  get <wire>           print the signal of a wire
+  trace <wire>         print a wire and everything it depends on
+  diff                 list the wires that changed since the baseline
+  solve <wire> <value> <free>...
+                       find the signals on the free wires giving <value> on <wire>
+  reset                remove every override
+  help                 print this message
+  quit                 leave the REPL";
+
+/// Maximum number of solutions printed by the `solve` command.
+const MAX_SOLUTIONS: usize = 16;
+
+/// Maximum number of free wires of the `solve` command, each one multiplies the search by 2^16.
+const MAX_FREE_WIRES: usize = 2;
+
+/* ---------- */
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command<'a> {
+    Set(&'a str, u16),
+    Get(&'a str),
+    Trace(&'a str),
+    Solve(&'a str, u16, Vec<&'a str>),
+    Diff,
+    Reset,
+    Help,
+    Quit,
+}
+
+impl<'a> Command<'a> {
+    fn parse(line: &'a str) -> Result<Option<Self>, String> {
+        let parts = line.split_ascii_whitespace().collect::<Vec<_>>();
+
+        let cmd = match parts.as_slice() {
+            [] => return Ok(None),
+            ["set", wire, value] => {
+                let value = value
+                    .parse::<u16>()
+                    .map_err(|_| format!("invalid signal value '{value}'"))?;
+                Self::Set(wire, value)
+            }
+            ["get", wire] => Self::Get(wire),
+            ["solve", wire, value, free @ ..] if !free.is_empty() => {
+                let value = value
+                    .parse::<u16>()
+                    .map_err(|_| format!("invalid signal value '{value}'"))?;
+
+                if free.len() > MAX_FREE_WIRES {
+                    return Err(format!(
+                        "at most {MAX_FREE_WIRES} free wires can be solved for"
+                    ));
+                }
+
+                Self::Solve(wire, value, free.to_vec())
+            }
+            ["trace", wire] => Self::Trace(wire),
+            ["diff"] => Self::Diff,
+            ["reset"] => Self::Reset,
+            ["help"] => Self::Help,
+            ["quit"] | ["exit"] => Self::Quit,
+            _ => return Err(format!("unknown command '{line}', try 'help'")),
+        };
+
+        Ok(Some(cmd))
+    }
+}
+
+/* ---------- */
+
+fn fmt_signal(signal: Option<u16>) -> String {
+    match signal {
+        Some(signal) => signal.to_string(),
+        None => String::from("?"),
+    }
+}
+
+/* ---------- */
+
+fn execute(cmd: Command, wires: &Wires, out: &mut impl Write) -> io::Result<()> {
+    match cmd {
+        Command::Set(id, value) => {
+            if !wires.set(id, value) {
+                writeln!(out, "no such wire '{id}'")?;
+            }
+        }
+        Command::Get(id) => match wires.wire(id) {
+            Some(_) => writeln!(out, "{id} = {}", fmt_signal(wires.query(id)))?,
+            None => writeln!(out, "no such wire '{id}'")?,
+        },
+        Command::Trace(id) => {
+            let trace = wires.trace(id);
+
+            if trace.is_empty() {
+                writeln!(out, "no such wire '{id}'")?;
+            }
+
+            for wire in trace {
+                let input = match wire.is_forced() {
+                    true => String::from("<set>"),
+                    false => wire.input_string(),
+                };
+
+                writeln!(
+                    out,
+                    "{} = {input} => {}",
+                    wire.id(),
+                    fmt_signal(wires.query(wire.id()))
+                )?;
+            }
+        }
+        Command::Solve(id, value, free) => {
+            let program = match Compiler::compile(wires) {
+                Ok(program) => program,
+                Err(err) => return writeln!(out, "can't compile the circuit: {err:?}"),
+            };
+
+            let solver = match Solver::new(&program, id, &free) {
+                Some(solver) => solver,
+                None => return writeln!(out, "no such wire"),
+            };
+
+            let solutions = solver
+                .solve(value)
+                .take(MAX_SOLUTIONS + 1)
+                .collect::<Vec<_>>();
+
+            if solutions.is_empty() {
+                writeln!(out, "no solution")?;
+            }
+
+            for solution in solutions.iter().take(MAX_SOLUTIONS) {
+                let assignment = free
+                    .iter()
+                    .zip(solution)
+                    .map(|(id, value)| format!("{id} = {value}"))
+                    .collect::<Vec<_>>();
+
+                writeln!(out, "  {}", assignment.join(", "))?;
+            }
+
+            if solutions.len() > MAX_SOLUTIONS {
+                writeln!(out, "  ...")?;
+            }
+        }
+        Command::Diff => {
+            for change in wires.changed() {
+                writeln!(
+                    out,
+                    "{}: {} -> {}",
+                    change.id,
+                    fmt_signal(change.baseline),
+                    fmt_signal(change.current)
+                )?;
+            }
+        }
+        Command::Reset => wires.clear_overrides(),
+        Command::Help => writeln!(out, "{HELP}")?,
+        Command::Quit => (),
+    }
+
+    Ok(())
+}
+
+/* ---------- */
+
+/// Reads commands from stdin, one per line, until `quit` or EOF.
+pub fn run(wires: &Wires) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    write!(stdout, "> ")?;
+    stdout.flush()?;
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+
+        match Command::parse(&line) {
+            Ok(Some(Command::Quit)) => break,
+            Ok(Some(cmd)) => execute(cmd, wires, &mut stdout)?,
+            Ok(None) => (),
+            Err(err) => writeln!(stdout, "{err}")?,
+        }
+
+        write!(stdout, "> ")?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}